@@ -0,0 +1,37 @@
+//! A compiled function over a million points: one `eval` call per point
+//! against `eval_columns` on all of them. `cargo bench -p calculator`.
+
+use calculator::{CompiledExpr, Settings};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::collections::HashMap;
+
+const FUNCTION: &str = "x^3 - 2x^2 + sin(x)/x + max(x, 0.5)";
+const POINTS: usize = 1_000_000;
+
+fn points() -> Vec<f64> {
+    (0..POINTS)
+        .map(|i| i as f64 / POINTS as f64 * 20.0 - 10.0)
+        .collect()
+}
+
+fn columns(c: &mut Criterion) {
+    let mut expr =
+        CompiledExpr::new(FUNCTION, &["x"], &Settings::default(), &HashMap::new()).unwrap();
+    let x = points();
+    let mut group = c.benchmark_group("1M points");
+    group.sample_size(10);
+    group.bench_function("eval per point", |b| {
+        b.iter(|| {
+            x.iter()
+                .map(|&x| expr.eval(&[x]).unwrap())
+                .collect::<Vec<f64>>()
+        })
+    });
+    group.bench_function("eval_columns", |b| {
+        b.iter(|| expr.eval_columns(&[black_box(&x)]).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, columns);
+criterion_main!(benches);
@@ -0,0 +1,41 @@
+//! Evaluating one function at many points, as plots do: from the source
+//! every time against compiling it once. `cargo bench -p calculator`.
+
+use calculator::{eval_in, CompiledExpr, Settings};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::collections::HashMap;
+
+const FUNCTION: &str = "x^3 - 2x^2 + sin(x)/x + max(x, 0.5)";
+const POINTS: usize = 1000;
+
+fn points() -> impl Iterator<Item = f64> {
+    (0..POINTS).map(|i| i as f64 / POINTS as f64 * 20.0 - 10.0)
+}
+
+fn per_call(c: &mut Criterion) {
+    let settings = Settings::default();
+    c.bench_function("eval_in, 1000 points", |b| {
+        b.iter(|| {
+            let mut variables = HashMap::new();
+            for x in points() {
+                variables.insert("x".to_string(), x);
+                black_box(eval_in(FUNCTION, &settings, &variables).unwrap());
+            }
+        })
+    });
+}
+
+fn compiled(c: &mut Criterion) {
+    let mut expr =
+        CompiledExpr::new(FUNCTION, &["x"], &Settings::default(), &HashMap::new()).unwrap();
+    c.bench_function("CompiledExpr, 1000 points", |b| {
+        b.iter(|| {
+            for x in points() {
+                black_box(expr.eval(&[x]).unwrap());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, per_call, compiled);
+criterion_main!(benches);
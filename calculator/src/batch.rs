@@ -0,0 +1,135 @@
+use super::*;
+use std::io::{Read, Write};
+use thiserror::Error;
+
+/// What stops a whole batch. Problems with single rows are [`RowError`]s.
+#[derive(Error, Debug)]
+pub enum BatchError {
+    /// One of the expressions does not compile.
+    #[error("{name}: {error}")]
+    Expression {
+        name: String,
+        error: CalculatorError,
+    },
+    /// An output column has the name of an input column or another output,
+    /// leading and trailing spaces aside.
+    #[error("{0} is already a column")]
+    DuplicateColumn(String),
+    /// Two input columns have the same header, expressions could not tell
+    /// them apart.
+    #[error("{0} is the header of more than one column")]
+    DuplicateHeader(String),
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+}
+
+/// A cell that could not be computed. `row` counts data rows from 1 and
+/// `column` is the output column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowError {
+    pub row: usize,
+    pub column: String,
+    pub error: CalculatorError,
+}
+
+/// Reads a CSV with a header from `input` and writes it to `output` with a
+/// column added for each `(name, expression)`. The expressions see the input
+/// columns as variables by their header, besides `constants`.
+///
+/// An expression that fails on a row, or reads a cell that is not a number,
+/// leaves its cell empty and gives a [`RowError`]; the run goes on. So does a
+/// row with more or fewer fields than the header, its input cells are cut or
+/// padded to fit and all its output cells are left empty.
+pub fn eval_csv<R: Read, W: Write>(
+    input: R,
+    output: W,
+    expressions: &[(&str, &str)],
+    settings: &Settings,
+    constants: &HashMap<String, f64>,
+) -> Result<Vec<RowError>, BatchError> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(input);
+    let mut writer = csv::Writer::from_writer(output);
+    let headers = reader.headers()?.clone();
+    let columns: Vec<&str> = headers.iter().map(str::trim).collect();
+    for (i, column) in columns.iter().enumerate() {
+        if columns[..i].contains(column) {
+            return Err(BatchError::DuplicateHeader(column.to_string()));
+        }
+    }
+    let mut compiled = Vec::with_capacity(expressions.len());
+    for (i, &(name, expression)) in expressions.iter().enumerate() {
+        let taken = columns.contains(&name.trim())
+            || expressions[..i]
+                .iter()
+                .any(|&(other, _)| other.trim() == name.trim());
+        if taken {
+            return Err(BatchError::DuplicateColumn(name.to_string()));
+        }
+        let expr =
+            CompiledExpr::new(expression, &columns, settings, constants).map_err(|error| {
+                BatchError::Expression {
+                    name: name.to_string(),
+                    error,
+                }
+            })?;
+        compiled.push((name, expr));
+    }
+    writer.write_record(
+        headers
+            .iter()
+            .chain(expressions.iter().map(|&(name, _)| name)),
+    )?;
+
+    let mut errors = Vec::new();
+    let mut values = vec![0.0; columns.len()];
+    let mut numbers = vec![true; columns.len()];
+    let mut record = csv::StringRecord::new();
+    let mut row = 0;
+    while reader.read_record(&mut record)? {
+        row += 1;
+        if record.len() != columns.len() {
+            for &(name, _) in expressions {
+                errors.push(RowError {
+                    row,
+                    column: name.to_string(),
+                    error: CalculatorError::Runtime(format!(
+                        "the row has {} fields, the header {}",
+                        record.len(),
+                        columns.len()
+                    )),
+                });
+            }
+            let mut cells: Vec<&str> = record.iter().collect();
+            cells.resize(columns.len(), "");
+            cells.resize(columns.len() + expressions.len(), "");
+            writer.write_record(&cells)?;
+            continue;
+        }
+        for (i, cell) in record.iter().enumerate() {
+            let parsed = cell.trim().parse();
+            numbers[i] = parsed.is_ok();
+            values[i] = parsed.unwrap_or(f64::NAN);
+        }
+        let mut cells: Vec<String> = record.iter().map(str::to_string).collect();
+        for (name, expr) in compiled.iter_mut() {
+            let result = match (0..columns.len()).find(|&i| expr.uses(i) && !numbers[i]) {
+                Some(i) => runtime!(format!("{} is not a number: {:?}", columns[i], &record[i])),
+                None => expr.eval(&values),
+            };
+            match result {
+                Ok(value) => cells.push(value.to_string()),
+                Err(error) => {
+                    errors.push(RowError {
+                        row,
+                        column: name.to_string(),
+                        error,
+                    });
+                    cells.push(String::new());
+                }
+            }
+        }
+        writer.write_record(&cells)?;
+    }
+    writer.flush().map_err(csv::Error::from)?;
+    Ok(errors)
+}
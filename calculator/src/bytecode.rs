@@ -0,0 +1,631 @@
+use super::*;
+#[cfg(feature = "gamma")]
+use mathru::special::gamma::gamma;
+use std::collections::HashMap;
+
+// Index into the register file, which holds the variables, then the
+// constants, then one temporary for each position of the stack the RPN
+// would have used.
+type Register = u16;
+
+// Rows `run_columns` takes through each instruction at a time.
+const LANES: usize = 256;
+
+/// What cannot be evaluated. It becomes an instruction, so that like a name
+/// in a branch not taken it only fails when it is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Failure {
+    UnknownName,
+    UnknownFunction,
+    Arity,
+}
+
+impl Failure {
+    pub(crate) fn message(self) -> &'static str {
+        match self {
+            Failure::UnknownName => "const number",
+            Failure::UnknownFunction => "function not found",
+            Failure::Arity => "wrong no of arguments",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Instr {
+    /// `r[dst] = r[src]`
+    Move {
+        dst: Register,
+        src: Register,
+    },
+    /// `r[dst] = op r[src]`
+    Unary {
+        op: Op,
+        dst: Register,
+        src: Register,
+    },
+    /// `r[dst] = r[a] op r[b]`
+    Binary {
+        op: Op,
+        dst: Register,
+        a: Register,
+        b: Register,
+    },
+    /// `r[dst] = function(r[args], ...)` with `function.arity()` arguments.
+    Call {
+        function: Function,
+        dst: Register,
+        args: Register,
+    },
+    Factorial {
+        dst: Register,
+        src: Register,
+    },
+    Jump {
+        target: u32,
+    },
+    JumpUnless {
+        condition: Register,
+        target: u32,
+    },
+    Fail(Failure),
+}
+
+/// An expression lowered to instructions for a register machine. Numbers
+/// are parsed, names resolved and the operands of every instruction known
+/// when it is compiled, so running it only does the arithmetic.
+pub(crate) struct Program {
+    code: Vec<Instr>,
+    // The register file to start from, with the constants in place.
+    registers: Vec<f64>,
+    result: Register,
+    // Which variables the code reads.
+    used: Vec<bool>,
+    word_size: Option<WordSize>,
+    angle: Angle,
+}
+
+impl Program {
+    /// Compiles `rpn`, in which `variables` name the first registers and
+    /// hide `consts` of the same name.
+    pub(crate) fn compile(
+        rpn: &[Tok],
+        variables: &[&str],
+        consts: &HashMap<String, f64>,
+        functions: &HashMap<String, Function>,
+        word_size: Option<WordSize>,
+        angle: Angle,
+    ) -> Result<Self, CalculatorError> {
+        let mut registers: Vec<f64> = vec![0.0; variables.len()];
+        let mut used = vec![false; variables.len()];
+        let mut operands = Vec::with_capacity(rpn.len());
+        for tok in rpn {
+            let value = match tok {
+                Tok::Number(number) => match number.parse::<f64>() {
+                    Ok(value) => value,
+                    Err(_) => return runtime!(format!("parse number")),
+                },
+                Tok::Const(name) => match variables
+                    .iter()
+                    .position(|&variable| variable == name.as_str())
+                {
+                    Some(slot) => {
+                        used[slot] = true;
+                        operands.push(Some(slot));
+                        continue;
+                    }
+                    None => match consts.get(name) {
+                        Some(&value) => value,
+                        None => {
+                            operands.push(None);
+                            continue;
+                        }
+                    },
+                },
+                Tok::List(_) | Tok::Index | &TOK_RANGE => {
+                    return runtime!(format!("lists cannot be compiled"));
+                }
+                _ => continue,
+            };
+            let value = word_size.map_or(value, |word_size| word_size.wrap(value));
+            let register = match registers[variables.len()..]
+                .iter()
+                .position(|r| r.to_bits() == value.to_bits())
+            {
+                Some(i) => variables.len() + i,
+                None => {
+                    registers.push(value);
+                    registers.len() - 1
+                }
+            };
+            operands.push(Some(register));
+        }
+        if registers.len() + rpn.len() > usize::from(Register::MAX) {
+            return runtime!(format!("expression too long"));
+        }
+        let mut compiler = Compiler {
+            code: Vec::with_capacity(rpn.len()),
+            stack: vec![],
+            temporaries: registers.len() as Register,
+            depth: 0,
+        };
+        let result = compiler.compile(rpn, &operands, functions)?;
+        registers.resize(registers.len() + compiler.depth, 0.0);
+        Ok(Program {
+            code: compiler.code,
+            registers,
+            result,
+            used,
+            word_size,
+            angle,
+        })
+    }
+
+    /// A register file for `run`, it can be reused between runs.
+    pub(crate) fn registers(&self) -> Vec<f64> {
+        self.registers.clone()
+    }
+
+    /// Whether the `i`th variable appears in the expression.
+    pub(crate) fn uses(&self, i: usize) -> bool {
+        self.used.get(i).copied().unwrap_or(false)
+    }
+
+    /// Runs the program with `values` in the variable registers.
+    pub(crate) fn run(
+        &self,
+        registers: &mut [f64],
+        values: &[f64],
+    ) -> Result<f64, CalculatorError> {
+        for (register, &value) in registers.iter_mut().zip(values) {
+            *register = self.wrap(value);
+        }
+        let mut pc = 0;
+        while let Some(&instr) = self.code.get(pc) {
+            pc += 1;
+            let r = |register: Register| registers[usize::from(register)];
+            let (dst, value) = match instr {
+                Instr::Move { dst, src } => (dst, r(src)),
+                Instr::Unary { op, dst, src } => (dst, unary(op, r(src), self.word_size)),
+                Instr::Binary { op, dst, a, b } => (dst, binary(op, r(a), r(b), self.word_size)),
+                Instr::Call {
+                    function,
+                    dst,
+                    args,
+                } => {
+                    let args = usize::from(args);
+                    (
+                        dst,
+                        function.apply(&registers[args..args + function.arity()], self.angle)?,
+                    )
+                }
+                Instr::Factorial { dst, src } => (dst, factorial(r(src))?),
+                Instr::Jump { target } => {
+                    pc = target as usize;
+                    continue;
+                }
+                Instr::JumpUnless { condition, target } => {
+                    if !truthy(r(condition)) {
+                        pc = target as usize;
+                    }
+                    continue;
+                }
+                Instr::Fail(failure) => return runtime!(failure.message().to_string()),
+            };
+            registers[usize::from(dst)] = self.wrap(value);
+        }
+        Ok(registers[usize::from(self.result)])
+    }
+
+    /// Runs the program for every row of `columns`, one slice of the same
+    /// length per variable. Each instruction goes over a block of rows at a
+    /// time; a block that a condition splits is run row by row instead.
+    pub(crate) fn run_columns(&self, columns: &[&[f64]]) -> Result<Vec<f64>, CalculatorError> {
+        let rows = columns.first().map_or(0, |column| column.len());
+        let mut results = Vec::with_capacity(rows);
+        let mut lanes = vec![0.0; self.registers.len() * LANES];
+        for (register, lane) in self
+            .registers
+            .iter()
+            .zip(lanes.chunks_mut(LANES))
+            .skip(columns.len())
+        {
+            lane.iter_mut().for_each(|x| *x = *register);
+        }
+        let mut scalar = None;
+        for start in (0..rows).step_by(LANES) {
+            let n = LANES.min(rows - start);
+            for (column, lane) in columns.iter().zip(lanes.chunks_mut(LANES)) {
+                for (x, &value) in lane.iter_mut().zip(&column[start..start + n]) {
+                    *x = self.wrap(value);
+                }
+            }
+            if self.run_lanes(&mut lanes, n)? {
+                let result = usize::from(self.result) * LANES;
+                results.extend_from_slice(&lanes[result..result + n]);
+            } else {
+                let registers = scalar.get_or_insert_with(|| self.registers());
+                let mut values = vec![0.0; columns.len()];
+                for row in start..start + n {
+                    for (value, column) in values.iter_mut().zip(columns) {
+                        *value = column[row];
+                    }
+                    results.push(self.run(registers, &values)?);
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    // Runs the code over the first `n` lanes of every register, false when a
+    // condition differs between them.
+    fn run_lanes(&self, lanes: &mut [f64], n: usize) -> Result<bool, CalculatorError> {
+        let mut pc = 0;
+        while let Some(&instr) = self.code.get(pc) {
+            pc += 1;
+            let dst = match instr {
+                Instr::Move { dst, src } => {
+                    let src = usize::from(src) * LANES;
+                    lanes.copy_within(src..src + n, usize::from(dst) * LANES);
+                    continue;
+                }
+                Instr::Unary { op, dst, src } => {
+                    match op {
+                        Op::Umin => map1(lanes, dst, src, n, |a| -a),
+                        Op::Sqrt => map1(lanes, dst, src, n, f64::sqrt),
+                        _ => map1(lanes, dst, src, n, |a| unary(op, a, self.word_size)),
+                    }
+                    dst
+                }
+                Instr::Binary { op, dst, a, b } => {
+                    match op {
+                        Op::Add => map2(lanes, dst, a, b, n, |a, b| a + b),
+                        Op::Sub => map2(lanes, dst, a, b, n, |a, b| a - b),
+                        Op::Mul => map2(lanes, dst, a, b, n, |a, b| a * b),
+                        Op::Div => map2(lanes, dst, a, b, n, |a, b| a / b),
+                        Op::Power => map2(lanes, dst, a, b, n, |a, b| a.powi(b as i32)),
+                        _ => map2(lanes, dst, a, b, n, |a, b| binary(op, a, b, self.word_size)),
+                    }
+                    dst
+                }
+                Instr::Call {
+                    function,
+                    dst,
+                    args,
+                } => {
+                    let args = usize::from(args) * LANES;
+                    let mut arguments = [0.0; 2];
+                    for i in 0..n {
+                        for (k, argument) in arguments[..function.arity()].iter_mut().enumerate() {
+                            *argument = lanes[args + k * LANES + i];
+                        }
+                        lanes[usize::from(dst) * LANES + i] =
+                            function.apply(&arguments[..function.arity()], self.angle)?;
+                    }
+                    dst
+                }
+                Instr::Factorial { dst, src } => {
+                    let (dst_lane, src) = (usize::from(dst) * LANES, usize::from(src) * LANES);
+                    for i in 0..n {
+                        lanes[dst_lane + i] = factorial(lanes[src + i])?;
+                    }
+                    dst
+                }
+                Instr::Jump { target } => {
+                    pc = target as usize;
+                    continue;
+                }
+                Instr::JumpUnless { condition, target } => {
+                    let condition = usize::from(condition) * LANES;
+                    let taken = lanes[condition..condition + n]
+                        .iter()
+                        .filter(|&&x| truthy(x))
+                        .count();
+                    if taken == 0 {
+                        pc = target as usize;
+                    } else if taken < n {
+                        return Ok(false);
+                    }
+                    continue;
+                }
+                Instr::Fail(failure) => return runtime!(failure.message().to_string()),
+            };
+            if self.word_size.is_some() {
+                let dst = usize::from(dst) * LANES;
+                for x in &mut lanes[dst..dst + n] {
+                    *x = self.wrap(*x);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    fn wrap(&self, x: f64) -> f64 {
+        self.word_size.map_or(x, |word_size| word_size.wrap(x))
+    }
+}
+
+// Lowers RPN by following the stack it would use: every position of the
+// stack gets a temporary register, operands that are variables or
+// constants are read where they are.
+struct Compiler {
+    code: Vec<Instr>,
+    stack: Vec<Register>,
+    temporaries: Register,
+    depth: usize,
+}
+
+impl Compiler {
+    fn compile(
+        &mut self,
+        rpn: &[Tok],
+        operands: &[Option<usize>],
+        functions: &HashMap<String, Function>,
+    ) -> Result<Register, CalculatorError> {
+        let mut operands = operands.iter();
+        // Code index of every token, for the jumps, and the stack where a
+        // jump lands.
+        let mut starts = vec![0; rpn.len() + 1];
+        let mut landings: HashMap<usize, Vec<Register>> = HashMap::new();
+        let mut reachable = true;
+        for (i, start) in starts.iter_mut().enumerate() {
+            if let Some(stack) = landings.get(&i) {
+                if reachable {
+                    // Both ways in must leave the value in the same register.
+                    self.settle();
+                } else {
+                    self.stack = stack.clone();
+                }
+                reachable = true;
+            }
+            *start = self.code.len();
+            let tok = match rpn.get(i) {
+                Some(tok) => tok,
+                None => break,
+            };
+            match tok {
+                Tok::Number(_) | Tok::Const(_) => match operands.next() {
+                    Some(&Some(register)) => self.push(register as Register),
+                    _ => self.fail(Failure::UnknownName, 0),
+                },
+                Tok::Operator(op) if is_unary(op) => {
+                    let src = self.stack.pop().unwrap();
+                    let dst = self.temporary();
+                    self.emit(Instr::Unary { op: *op, dst, src });
+                }
+                Tok::Operator(op) => {
+                    let b = self.stack.pop().unwrap();
+                    let a = self.stack.pop().unwrap();
+                    let dst = self.temporary();
+                    self.emit(Instr::Binary { op: *op, dst, a, b });
+                }
+                Tok::Factorial => {
+                    let src = self.stack.pop().unwrap();
+                    let dst = self.temporary();
+                    self.emit(Instr::Factorial { dst, src });
+                }
+                Tok::Call(name, arity) => match functions.get(name) {
+                    Some(&function) if function.arity() == *arity => {
+                        // The arguments have to be next to each other.
+                        let first = self.stack.len() - arity;
+                        for position in first..self.stack.len() {
+                            self.settle_at(position);
+                        }
+                        self.stack.truncate(first);
+                        let dst = self.temporary();
+                        self.emit(Instr::Call {
+                            function,
+                            dst,
+                            args: dst,
+                        });
+                    }
+                    Some(_) => self.fail(Failure::Arity, *arity),
+                    None => self.fail(Failure::UnknownFunction, *arity),
+                },
+                Tok::Jump(target) => {
+                    self.settle();
+                    landings
+                        .entry(*target)
+                        .or_insert_with(|| self.stack.clone());
+                    self.emit(Instr::Jump {
+                        target: *target as u32,
+                    });
+                    reachable = false;
+                }
+                Tok::JumpUnless(target) => {
+                    let condition = self.stack.pop().unwrap();
+                    landings
+                        .entry(*target)
+                        .or_insert_with(|| self.stack.clone());
+                    self.emit(Instr::JumpUnless {
+                        condition,
+                        target: *target as u32,
+                    });
+                }
+                _ => {}
+            }
+        }
+        for instr in &mut self.code {
+            if let Instr::Jump { target } | Instr::JumpUnless { target, .. } = instr {
+                *target = starts[*target as usize] as u32;
+            }
+        }
+        match self.stack.last() {
+            Some(&result) => Ok(result),
+            None => runtime!(format!("empty expression")),
+        }
+    }
+
+    fn emit(&mut self, instr: Instr) {
+        self.code.push(instr);
+    }
+
+    fn push(&mut self, register: Register) {
+        self.stack.push(register);
+        self.depth = self.depth.max(self.stack.len());
+    }
+
+    // The temporary of the next position, pushed.
+    fn temporary(&mut self) -> Register {
+        let register = self.temporaries + self.stack.len() as Register;
+        self.push(register);
+        register
+    }
+
+    // Moves the value at `position` into its temporary.
+    fn settle_at(&mut self, position: usize) {
+        let dst = self.temporaries + position as Register;
+        if self.stack[position] != dst {
+            self.emit(Instr::Move {
+                dst,
+                src: self.stack[position],
+            });
+            self.stack[position] = dst;
+        }
+        self.depth = self.depth.max(position + 1);
+    }
+
+    fn settle(&mut self) {
+        if !self.stack.is_empty() {
+            self.settle_at(self.stack.len() - 1);
+        }
+    }
+
+    fn fail(&mut self, failure: Failure, arity: usize) {
+        let len = self.stack.len();
+        self.stack.truncate(len - arity);
+        self.emit(Instr::Fail(failure));
+        self.temporary();
+    }
+}
+
+// `r[dst] = f(r[src])` over the first `n` lanes.
+fn map1(lanes: &mut [f64], dst: Register, src: Register, n: usize, f: impl Fn(f64) -> f64) {
+    let (dst, src) = (usize::from(dst) * LANES, usize::from(src) * LANES);
+    lanes.copy_within(src..src + n, dst);
+    for x in &mut lanes[dst..dst + n] {
+        *x = f(*x);
+    }
+}
+
+// `r[dst] = f(r[a], r[b])` over the first `n` lanes.
+fn map2(
+    lanes: &mut [f64],
+    dst: Register,
+    a: Register,
+    b: Register,
+    n: usize,
+    f: impl Fn(f64, f64) -> f64,
+) {
+    if b == dst {
+        if a == dst {
+            return map1(lanes, dst, a, n, |x| f(x, x));
+        }
+        let (dst, a) = split(lanes, dst, a);
+        for (x, &y) in dst[..n].iter_mut().zip(&a[..n]) {
+            *x = f(y, *x);
+        }
+    } else {
+        if a != dst {
+            let a = usize::from(a) * LANES;
+            lanes.copy_within(a..a + n, usize::from(dst) * LANES);
+        }
+        let (dst, b) = split(lanes, dst, b);
+        for (x, &y) in dst[..n].iter_mut().zip(&b[..n]) {
+            *x = f(*x, y);
+        }
+    }
+}
+
+// The lanes of `dst` to write and of `src` to read, two different registers.
+fn split(lanes: &mut [f64], dst: Register, src: Register) -> (&mut [f64], &[f64]) {
+    let (dst, src) = (usize::from(dst) * LANES, usize::from(src) * LANES);
+    if dst < src {
+        let (low, high) = lanes.split_at_mut(src);
+        (&mut low[dst..dst + LANES], &high[..LANES])
+    } else {
+        let (low, high) = lanes.split_at_mut(dst);
+        (&mut high[..LANES], &low[src..src + LANES])
+    }
+}
+
+pub(crate) fn is_unary(op: &Op) -> bool {
+    matches!(
+        op,
+        Op::Umin | Op::Sqrt | Op::Cbrt | Op::Not | Op::BitNot | Op::And | Op::Or
+    )
+}
+
+pub(crate) fn unary(op: Op, a: f64, word_size: Option<WordSize>) -> f64 {
+    match op {
+        Op::Umin => -a,
+        Op::Sqrt => a.sqrt(),
+        Op::Cbrt => a.cbrt(),
+        Op::Not => boolean(!truthy(a)),
+        Op::BitNot => wrap(!(a as i128) as f64, word_size),
+        // The left operand was already consumed by the jump emitted in `RPN`.
+        Op::And | Op::Or => boolean(truthy(a)),
+        _ => unreachable!(),
+    }
+}
+
+pub(crate) fn binary(op: Op, a: f64, b: f64, word_size: Option<WordSize>) -> f64 {
+    match op {
+        Op::Add => a + b,
+        Op::Sub => a - b,
+        Op::Mul => a * b,
+        Op::Div => a / b,
+        Op::Mod => a % b,
+        Op::Power => a.powi(b as i32),
+        Op::Lt => boolean(a < b),
+        Op::Le => boolean(a <= b),
+        Op::Gt => boolean(a > b),
+        Op::Ge => boolean(a >= b),
+        Op::Eq => boolean(a == b),
+        Op::Ne => boolean(a != b),
+        Op::BitAnd | Op::BitOr | Op::BitXor | Op::Shl | Op::Shr => {
+            let (a, b) = (a as i128, b as i128);
+            let z = match op {
+                Op::BitAnd => a & b,
+                Op::BitOr => a | b,
+                Op::BitXor => a ^ b,
+                Op::Shl => a.checked_shl(b as u32).unwrap_or(0),
+                _ => a
+                    .checked_shr(b as u32)
+                    .unwrap_or(if a < 0 { -1 } else { 0 }),
+            };
+            wrap(z as f64, word_size)
+        }
+        _ => unreachable!(),
+    }
+}
+
+// Without a word size the bitwise operators give plain numbers.
+fn wrap(x: f64, word_size: Option<WordSize>) -> f64 {
+    word_size.map_or(x, |word_size| word_size.wrap(x))
+}
+
+pub(crate) fn truthy(x: f64) -> bool {
+    x != 0.0 && !x.is_nan()
+}
+
+fn boolean(z: bool) -> f64 {
+    if z {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+#[cfg(feature = "gamma")]
+pub(crate) fn factorial(x: f64) -> Result<f64, CalculatorError> {
+    Ok(gamma(x + 1.0))
+}
+
+// Without the gamma function only whole numbers have a factorial.
+#[cfg(not(feature = "gamma"))]
+pub(crate) fn factorial(x: f64) -> Result<f64, CalculatorError> {
+    if x < 0.0 || x.fract() != 0.0 {
+        return runtime!(format!("{}! needs the `gamma` feature", x));
+    }
+    Ok((1..=x.min(171.0) as u32).map(f64::from).product())
+}
@@ -0,0 +1,81 @@
+use super::*;
+use std::collections::HashMap;
+
+/// An expression parsed and resolved once, to be evaluated many times with
+/// different values for its variables. Evaluating does no parsing, no name
+/// lookups and no allocation.
+pub struct CompiledExpr {
+    program: Program,
+    registers: Vec<f64>,
+    variables: Vec<String>,
+}
+
+impl CompiledExpr {
+    /// Compiles `expression`, in which `variables` are the slots filled by
+    /// `eval` and the names in `constants` keep their values. Variables hide
+    /// constants, both hide the built-in ones.
+    pub fn new(
+        expression: &str,
+        variables: &[&str],
+        settings: &Settings,
+        constants: &HashMap<String, f64>,
+    ) -> Result<Self, CalculatorError> {
+        let program = compile(expression, settings, constants)?.program(variables)?;
+        Ok(CompiledExpr {
+            registers: program.registers(),
+            program,
+            variables: variables
+                .iter()
+                .map(|variable| variable.to_string())
+                .collect(),
+        })
+    }
+
+    /// The names of the slots, in the order `eval` takes their values.
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    /// Whether the expression reads `variables()[i]`, the value of one it
+    /// does not read is ignored.
+    pub fn uses(&self, i: usize) -> bool {
+        self.program.uses(i)
+    }
+
+    /// The value with `values[i]` for `variables()[i]`.
+    pub fn eval(&mut self, values: &[f64]) -> Result<f64, CalculatorError> {
+        if values.len() != self.variables.len() {
+            return runtime!(format!(
+                "expected {} values, got {}",
+                self.variables.len(),
+                values.len()
+            ));
+        }
+        self.program.run(&mut self.registers, values)
+    }
+
+    /// The values for many rows at once, `columns[i]` holding those of
+    /// `variables()[i]` and all of the same length. Much faster than calling
+    /// `eval` for each row, the result is the same except that it fails if
+    /// the expression fails for any row. Without variables there are no rows.
+    pub fn eval_columns(&self, columns: &[&[f64]]) -> Result<Vec<f64>, CalculatorError> {
+        if columns.len() != self.variables.len() {
+            return runtime!(format!(
+                "expected {} columns, got {}",
+                self.variables.len(),
+                columns.len()
+            ));
+        }
+        if let Some(column) = columns
+            .iter()
+            .find(|column| column.len() != columns[0].len())
+        {
+            return runtime!(format!(
+                "columns of {} and {} values",
+                columns[0].len(),
+                column.len()
+            ));
+        }
+        self.program.run_columns(columns)
+    }
+}
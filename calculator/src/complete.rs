@@ -0,0 +1,178 @@
+use super::*;
+use std::ops::Range;
+
+/// Name, signature and description of everything an expression can call.
+// `if` is handled by the parser and `plot` by `Calculator`, the rest by
+// `SimpleCodeGen`.
+pub const FUNCTIONS: [(&str, &str, &str); 27] = [
+    (
+        "acos",
+        "acos(x)",
+        "Inverse cosine, in the current angle unit",
+    ),
+    ("asin", "asin(x)", "Inverse sine, in the current angle unit"),
+    ("cbrt", "cbrt(x)", "Cube root"),
+    ("cos", "cos(x)", "Cosine of an angle in the current unit"),
+    ("det", "det(matrix)", "Determinant of a square matrix"),
+    (
+        "eig",
+        "eig(matrix)",
+        "Real eigenvalues of a square matrix, from the smallest",
+    ),
+    (
+        "if",
+        "if(condition, then, else)",
+        "Evaluates only the branch that is taken",
+    ),
+    ("inv", "inv(matrix)", "Inverse of a square matrix"),
+    ("lb", "lb(x)", "Binary logarithm"),
+    (
+        "len",
+        "len(list)",
+        "Number of elements, or of rows of a matrix",
+    ),
+    ("lg", "lg(x)", "Common logarithm"),
+    ("ln", "ln(x)", "Natural logarithm"),
+    ("log", "log(base, x)", "Logarithm to any base"),
+    ("log10", "log10(x)", "Common logarithm"),
+    ("log2", "log2(x)", "Binary logarithm"),
+    ("max", "max(a, b)", "The larger argument"),
+    ("mean", "mean(list)", "Average of the elements"),
+    ("min", "min(a, b)", "The smaller argument"),
+    (
+        "plot",
+        "plot(f, ..., from, to)",
+        "Draws functions of x between two bounds",
+    ),
+    ("prod", "prod(list)", "Product of the elements"),
+    ("rank", "rank(matrix)", "Number of independent rows"),
+    (
+        "rnd",
+        "rnd(low, high)",
+        "Uniformly distributed random number",
+    ),
+    ("sin", "sin(x)", "Sine of an angle in the current unit"),
+    ("solve", "solve(A, b)", "The x with A x = b"),
+    ("sqrt", "sqrt(x)", "Square root"),
+    ("sum", "sum(list)", "Sum of the elements"),
+    ("transpose", "transpose(matrix)", "Rows turned into columns"),
+];
+
+/// Name and description of the built-in constants.
+pub const CONSTANTS: [(&str, &str); 7] = [
+    ("e", "Euler's number"),
+    ("false", "Zero"),
+    ("Inf", "Positive infinity"),
+    ("Infinity", "Positive infinity"),
+    ("NaN", "Not a number"),
+    ("pi", "Ratio of a circle's circumference to its diameter"),
+    ("true", "One"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    Function,
+    Constant,
+    Variable,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub name: String,
+    pub kind: CompletionKind,
+    pub signature: String,
+    pub doc: String,
+    /// Byte range of the name being typed, which the completion replaces.
+    pub range: Range<usize>,
+}
+
+impl Completion {
+    /// What replaces `range`, functions come with their opening parenthesis.
+    pub fn text(&self) -> String {
+        match self.kind {
+            CompletionKind::Function => format!("{}(", self.name),
+            _ => self.name.clone(),
+        }
+    }
+}
+
+/// Names starting with the identifier before the byte offset `cursor`,
+/// ignoring case. Names that are already typed out in full are left out,
+/// except functions which still lack their parenthesis.
+pub fn complete(
+    expression: &str,
+    cursor: usize,
+    variables: &HashMap<String, f64>,
+) -> Vec<Completion> {
+    let range = match word_at(expression, cursor) {
+        Some(range) => range,
+        None => return vec![],
+    };
+    let prefix = expression[range.start..cursor].to_lowercase();
+    let word = &expression[range.clone()];
+    let mut completions = vec![];
+    let mut add = |name: &str, kind, signature: String, doc: String| {
+        let full = name == word && kind != CompletionKind::Function;
+        if name.to_lowercase().starts_with(&prefix) && !full {
+            completions.push(Completion {
+                name: name.to_string(),
+                kind,
+                signature,
+                doc,
+                range: range.clone(),
+            });
+        }
+    };
+    let mut names: Vec<(&String, &f64)> = variables.iter().collect();
+    names.sort_by(|a, b| a.0.cmp(b.0));
+    let formatter = Formatter::default();
+    for (name, &value) in names {
+        add(
+            name,
+            CompletionKind::Variable,
+            name.clone(),
+            format!("= {}", formatter.format(value)),
+        );
+    }
+    for &(name, signature, doc) in FUNCTIONS.iter() {
+        add(
+            name,
+            CompletionKind::Function,
+            signature.to_string(),
+            doc.to_string(),
+        );
+    }
+    for &(name, doc) in CONSTANTS.iter() {
+        add(
+            name,
+            CompletionKind::Constant,
+            name.to_string(),
+            doc.to_string(),
+        );
+    }
+    // Matching case first, then the shorter names that are closer to done.
+    let original = &expression[range.start..cursor];
+    completions.sort_by_key(|completion| {
+        (
+            !completion.name.starts_with(original),
+            completion.name.len(),
+        )
+    });
+    completions
+}
+
+// The identifier the cursor is in or right after. Leading digits belong to
+// a number in front of it, as in `2pi`.
+fn word_at(expression: &str, cursor: usize) -> Option<Range<usize>> {
+    let before = expression.get(..cursor)?;
+    let start = before
+        .trim_end_matches(|c: char| c.is_ascii_alphanumeric())
+        .len();
+    let start = start + before[start..].find(|c: char| !c.is_ascii_digit())?;
+    let after = &expression[cursor..];
+    let end = cursor
+        + after
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(after.len());
+    Some(start..end)
+}
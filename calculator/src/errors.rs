@@ -0,0 +1,67 @@
+use std::ops::Range;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum CalculatorError {
+    #[error("Runtime: {0}")]
+    Runtime(String),
+    #[error("Syntax: {0} at {1}")]
+    Syntax(String, usize),
+}
+
+impl CalculatorError {
+    /// Byte range of the offending token in `expression`. An error at the
+    /// end of the input gives an empty range there.
+    pub fn span(&self, expression: &str) -> Option<Range<usize>> {
+        match self {
+            CalculatorError::Syntax(_, position) => {
+                let rest = expression.get(*position..)?;
+                let len = match rest.chars().next() {
+                    Some(c) if c.is_alphanumeric() => rest
+                        .find(|c: char| !c.is_alphanumeric() && c != '.' && c != '_')
+                        .unwrap_or(rest.len()),
+                    Some(c) => c.len_utf8(),
+                    None => 0,
+                };
+                Some(*position..*position + len)
+            }
+            CalculatorError::Runtime(_) => None,
+        }
+    }
+
+    /// Whether `expression` ran out before it was complete, like `1+` or
+    /// `max(2,`, rather than containing a mistake.
+    pub fn is_incomplete(&self, expression: &str) -> bool {
+        match self {
+            CalculatorError::Syntax(_, position) => {
+                matches!(expression.get(*position..), Some(rest) if rest.trim().is_empty())
+            }
+            CalculatorError::Runtime(_) => false,
+        }
+    }
+
+    /// Moves the position of a syntax error found in a slice of a longer
+    /// expression that starts `offset` bytes in.
+    pub fn offset(self, offset: usize) -> Self {
+        match self {
+            CalculatorError::Syntax(message, position) => {
+                CalculatorError::Syntax(message, position + offset)
+            }
+            error => error,
+        }
+    }
+}
+
+
+macro_rules! runtime {
+    ($message:expr) => {
+        Err( CalculatorError::Runtime($message))
+    };
+}
+
+macro_rules! syntax {
+    ($message:expr, $position:expr) => {
+        Err(CalculatorError::Syntax($message, $position))
+    };
+}
+
@@ -0,0 +1,201 @@
+use super::{radix_prefix, to_radix, Value, WordSize, MAX_SAFE_INTEGER};
+
+const SI_PREFIXES: [&str; 17] = [
+    "y", "z", "a", "f", "p", "n", "µ", "m", "", "k", "M", "G", "T", "P", "E", "Z", "Y",
+];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Notation {
+    Auto,
+    Fixed(usize),
+    Scientific,
+    Engineering,
+    Significant(usize),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Formatter {
+    pub notation: Notation,
+    /// Significant digits kept by `Auto`, `Scientific` and `Engineering`.
+    /// Anything past the 15th digit of an `f64` is float noise.
+    pub precision: usize,
+    pub thousands_separator: Option<char>,
+    pub decimal_mark: char,
+    /// Base of whole numbers, numbers with a fraction stay decimal.
+    pub radix: u32,
+    /// Shows negative numbers in two's complement of the word in a radix
+    /// other than 10, see [`Calculator::set_word_size`](super::Calculator::set_word_size).
+    pub word_size: Option<WordSize>,
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Formatter {
+            notation: Notation::Auto,
+            precision: 15,
+            thousands_separator: None,
+            decimal_mark: '.',
+            radix: 10,
+            word_size: None,
+        }
+    }
+}
+
+impl Formatter {
+    pub fn new(notation: Notation) -> Self {
+        Formatter {
+            notation,
+            ..Formatter::default()
+        }
+    }
+
+    pub fn format(&self, x: f64) -> String {
+        if x.is_nan() {
+            return "NaN".to_string();
+        }
+        if x.is_infinite() {
+            return if x < 0.0 { "-Infinity" } else { "Infinity" }.to_string();
+        }
+        if let Some(text) = self.format_radix(x) {
+            return text;
+        }
+        let negative = x < 0.0;
+        let x = x.abs();
+        let body = match self.notation {
+            Notation::Auto => {
+                let (digits, exp) = decompose(x, self.precision);
+                if (-7..21).contains(&exp) {
+                    self.plain(&digits, exp, true)
+                } else {
+                    self.scientific(&digits, exp)
+                }
+            }
+            Notation::Fixed(n) => {
+                let s = format!("{:.*}", n, x);
+                let mut parts = s.split('.');
+                let int = parts.next().unwrap();
+                let frac = parts.next().unwrap_or("");
+                self.join(int, frac)
+            }
+            Notation::Scientific => {
+                let (digits, exp) = decompose(x, self.precision);
+                self.scientific(&digits, exp)
+            }
+            Notation::Engineering => {
+                let (digits, exp) = decompose(x, self.precision);
+                let eng = exp.div_euclid(3) * 3;
+                let mantissa = self.plain(&digits, exp - eng, true);
+                match SI_PREFIXES.get((eng / 3 + 8) as usize) {
+                    Some(prefix) if x != 0.0 => format!("{}{}", mantissa, prefix),
+                    _ if x == 0.0 => mantissa,
+                    _ => format!("{}e{}", mantissa, eng),
+                }
+            }
+            Notation::Significant(n) => {
+                let (digits, exp) = decompose(x, n.max(1));
+                if (-7..21).contains(&exp) {
+                    self.plain(&digits, exp, false)
+                } else {
+                    self.scientific(&digits, exp)
+                }
+            }
+        };
+        if negative && body.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            format!("-{}", body)
+        } else {
+            body
+        }
+    }
+
+    /// Lists as `[1, 2, 3]` and matrices as `[[1, 2], [3, 4]]`, with `;`
+    /// between the elements where the decimal mark is a comma.
+    pub fn format_value(&self, value: &Value) -> String {
+        let separator = if self.decimal_mark == ',' { "; " } else { ", " };
+        let list = |list: &[f64]| {
+            let elements: Vec<String> = list.iter().map(|&x| self.format(x)).collect();
+            format!("[{}]", elements.join(separator))
+        };
+        match value {
+            Value::Number(x) => self.format(*x),
+            Value::List(elements) => list(elements),
+            Value::Matrix(matrix) => {
+                let rows: Vec<String> = (0..matrix.rows()).map(|i| list(matrix.row(i))).collect();
+                format!("[{}]", rows.join(separator))
+            }
+        }
+    }
+
+    // Whole numbers in a radix other than 10 with its prefix, like `0xFF`.
+    fn format_radix(&self, x: f64) -> Option<String> {
+        if self.radix == 10 || x.fract() != 0.0 || x.abs() > MAX_SAFE_INTEGER as f64 {
+            return None;
+        }
+        match self.word_size {
+            Some(word_size) => word_size.format(x, self.radix),
+            None => {
+                let digits = to_radix(x.abs() as u128, self.radix)?;
+                let sign = if x < 0.0 { "-" } else { "" };
+                Some(format!("{}{}{}", sign, radix_prefix(self.radix), digits))
+            }
+        }
+    }
+
+    fn scientific(&self, digits: &str, exp: i32) -> String {
+        let mantissa = self.plain(digits, 0, true);
+        if exp == 0 {
+            mantissa
+        } else {
+            format!("{}e{}", mantissa, exp)
+        }
+    }
+
+    // `digits` is read as d.ddd × 10^exp.
+    fn plain(&self, digits: &str, exp: i32, trim: bool) -> String {
+        let (int, frac) = if exp >= 0 {
+            let split = exp as usize + 1;
+            if digits.len() > split {
+                (digits[..split].to_string(), digits[split..].to_string())
+            } else {
+                (format!("{:0<1$}", digits, split), "".to_string())
+            }
+        } else {
+            (
+                "0".to_string(),
+                format!("{}{}", "0".repeat((-exp - 1) as usize), digits),
+            )
+        };
+        let frac = if trim {
+            frac.trim_end_matches('0')
+        } else {
+            &frac
+        };
+        self.join(&int, frac)
+    }
+
+    fn join(&self, int: &str, frac: &str) -> String {
+        let mut s = String::new();
+        for (i, c) in int.chars().enumerate() {
+            if let Some(separator) = self.thousands_separator {
+                if i > 0 && i % 3 == int.len() % 3 {
+                    s.push(separator);
+                }
+            }
+            s.push(c);
+        }
+        if !frac.is_empty() {
+            s.push(self.decimal_mark);
+            s.push_str(frac);
+        }
+        s
+    }
+}
+
+/// Rounds `x` to `precision` significant digits, returning the digits and
+/// the decimal exponent of the first one.
+fn decompose(x: f64, precision: usize) -> (String, i32) {
+    let s = format!("{:.*e}", precision.max(1) - 1, x);
+    let mut parts = s.split('e');
+    let mantissa = parts.next().unwrap().replace('.', "");
+    let exp = parts.next().unwrap().parse::<i32>().unwrap();
+    (mantissa, exp)
+}
@@ -0,0 +1,126 @@
+use super::*;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    Number,
+    Constant,
+    Variable,
+    Function,
+    Operator,
+    Paren,
+    Separator,
+    /// A name that is neither built in nor a variable.
+    Unknown,
+    /// Input the lexer gave up on.
+    Invalid,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub highlight: Highlight,
+    pub range: Range<usize>,
+}
+
+impl Span {
+    fn new(highlight: Highlight, range: Range<usize>) -> Self {
+        Span { highlight, range }
+    }
+}
+
+/// Splits `expression` into highlighted spans, in order and without the
+/// whitespace between them. Everything from the first character the lexer
+/// rejects on is a single `Invalid` span.
+pub fn highlight(expression: &str, locale: Locale, variables: &HashMap<String, f64>) -> Vec<Span> {
+    let mut spans = vec![];
+    let (name, rest) = split_assignment(expression);
+    let offset = expression.len() - rest.len();
+    if let Some(name) = name {
+        let start = expression.find(name).unwrap_or(0);
+        spans.push(Span::new(Highlight::Variable, start..start + name.len()));
+        spans.push(Span::new(Highlight::Operator, offset - 1..offset));
+    }
+    let builtins = SimpleCodeGen::new(vec![]);
+    let plot = Plot::is_plot(rest);
+    let (tokens, valid) = lex_prefix(rest, locale);
+    for (i, token) in tokens.iter().enumerate() {
+        let start = token.pos;
+        let end = tokens.get(i + 1).map_or(valid, |next| next.pos);
+        let end = start + rest[start..end].trim_end().len();
+        let (start, end) = (offset + start, offset + end);
+        let highlight = match &token.tok {
+            Tok::End => break,
+            Tok::Number(_) => Highlight::Number,
+            Tok::Const(name) if builtins.has_const(name) => Highlight::Constant,
+            Tok::Const(name) if variables.contains_key(name) || plot && name == PLOT_VARIABLE => {
+                Highlight::Variable
+            }
+            Tok::Const(_) => Highlight::Unknown,
+            Tok::Call(name, _) => {
+                // The span covers `name(`, the parenthesis gets its own.
+                let known =
+                    builtins.has_function(name) || name == "if" || plot && name == "plot" && i == 0;
+                let highlight = if known {
+                    Highlight::Function
+                } else {
+                    Highlight::Unknown
+                };
+                spans.push(Span::new(highlight, start..start + name.len()));
+                spans.push(Span::new(Highlight::Paren, end - 1..end));
+                continue;
+            }
+            Tok::LParen | Tok::RParen | Tok::LBracket | Tok::RBracket => Highlight::Paren,
+            Tok::Comma => Highlight::Separator,
+            _ => Highlight::Operator,
+        };
+        spans.push(Span::new(highlight, start..end));
+    }
+    if !rest[valid..].trim().is_empty() {
+        spans.push(Span::new(
+            Highlight::Invalid,
+            offset + valid..expression.len(),
+        ));
+    }
+    spans
+}
+
+// Tokens of the longest prefix the lexer accepts, with its length.
+fn lex_prefix(expression: &str, locale: Locale) -> (Vec<Token>, usize) {
+    let mut valid = expression.len();
+    loop {
+        match parse_tokens_with(expression[..valid].to_string(), locale) {
+            Ok(tokens) => return (tokens, valid),
+            Err(CalculatorError::Syntax(_, position)) if position < valid => valid = position,
+            Err(_) => return (vec![], 0),
+        }
+    }
+}
+
+/// Indices in `spans` of the parenthesis next to the byte offset `cursor`
+/// and the one it pairs with. A parenthesis just before the cursor wins.
+pub fn matching_paren(spans: &[Span], expression: &str, cursor: usize) -> Option<(usize, usize)> {
+    let is_paren = |i: &usize| spans[*i].highlight == Highlight::Paren;
+    let parens: Vec<usize> = (0..spans.len()).filter(is_paren).collect();
+    let at = parens
+        .iter()
+        .position(|&i| spans[i].range.end == cursor)
+        .or_else(|| parens.iter().position(|&i| spans[i].range.start == cursor))?;
+    let opening = |i: usize| expression[spans[i].range.clone()].ends_with('(');
+    let mut depth = 0;
+    if opening(parens[at]) {
+        for &i in &parens[at..] {
+            depth += if opening(i) { 1 } else { -1 };
+            if depth == 0 {
+                return Some((parens[at], i));
+            }
+        }
+    } else {
+        for &i in parens[..=at].iter().rev() {
+            depth += if opening(i) { -1 } else { 1 };
+            if depth == 0 {
+                return Some((i, parens[at]));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,232 @@
+use super::*;
+use anyhow::Context;
+
+pub fn parse_tokens(s: String) -> Result<Vec<Token>, CalculatorError> {
+    parse_tokens_with(s, Locale::default())
+}
+
+pub fn parse_tokens_with(mut s: String, locale: Locale) -> Result<Vec<Token>, CalculatorError> {
+    if let Some(position) = s.find('$') {
+        return syntax!(format!("syntax 1"), position);
+    } else {
+        s.push('$');
+        let mut it = s.char_indices().peekable();
+        let mut res = vec![];
+        while let Some((i, c)) = it.next() {
+            let token = match c {
+                '$' => Token::new(Tok::End, i),
+                _ if c == locale.argument_separator() => Token::new(Tok::Comma, i),
+                '!' => {
+                    if let Some((_, '=')) = it.peek() {
+                        it.next();
+                        Token::new(TOK_NE, i)
+                    } else if let Some(true) = res.last().map(|t: &Token| t.tok.is_operand()) {
+                        Token::new(Tok::Factorial, i)
+                    } else {
+                        Token::new(TOK_NOT, i)
+                    }
+                }
+                '<' | '>' if it.peek().map(|&(_, n)| n) == Some(c) => {
+                    it.next();
+                    if c == '<' {
+                        Token::new(TOK_SHL, i)
+                    } else {
+                        Token::new(TOK_SHR, i)
+                    }
+                }
+                '<' | '>' | '=' => {
+                    let eq = if let Some((_, '=')) = it.peek() {
+                        it.next();
+                        true
+                    } else {
+                        false
+                    };
+                    match (c, eq) {
+                        ('<', false) => Token::new(TOK_LT, i),
+                        ('<', true) => Token::new(TOK_LE, i),
+                        ('>', false) => Token::new(TOK_GT, i),
+                        ('>', true) => Token::new(TOK_GE, i),
+                        ('=', true) => Token::new(TOK_EQ, i),
+                        _ => return syntax!(format!("syntax 3"), i),
+                    }
+                }
+                '≤' => Token::new(TOK_LE, i),
+                '≥' => Token::new(TOK_GE, i),
+                '≠' => Token::new(TOK_NE, i),
+                '&' | '|' => {
+                    let double = if let Some(&(_, next)) = it.peek() {
+                        next == c
+                    } else {
+                        false
+                    };
+                    if double {
+                        it.next();
+                    }
+                    match (c, double) {
+                        ('&', true) => Token::new(TOK_AND, i),
+                        ('|', true) => Token::new(TOK_OR, i),
+                        ('&', false) => Token::new(TOK_BIT_AND, i),
+                        _ => Token::new(TOK_BIT_OR, i),
+                    }
+                }
+                '~' => Token::new(TOK_BIT_NOT, i),
+                '⊕' => Token::new(TOK_BIT_XOR, i),
+                '?' => Token::new(Tok::Question, i),
+                ':' => Token::new(Tok::Colon, i),
+                '(' => Token::new(Tok::LParen, i),
+                ')' => Token::new(Tok::RParen, i),
+                '[' => Token::new(Tok::LBracket, i),
+                ']' => Token::new(Tok::RBracket, i),
+                '.' if it.peek().map(|&(_, n)| n) == Some('.') => {
+                    it.next();
+                    Token::new(TOK_RANGE, i)
+                }
+                '%' => Token::new(TOK_MOD, i),
+                '+' => Token::new(TOK_ADD, i),
+                '-' => Token::new(TOK_SUB, i),
+                '*' | '×' => Token::new(TOK_MUL, i),
+                '/' | '÷' => Token::new(TOK_DIV, i),
+                '√' => Token::new(TOK_SQRT, i),
+                '∛' => Token::new(TOK_CBRT, i),
+                '^' => Token::new(TOK_POWER, i),
+                'π' => Token::new(Tok::Const(format!("{}", c)), i),
+                '0' if radix(it.peek()).is_some() => {
+                    let radix = radix(it.next().as_ref()).unwrap();
+                    let mut digits = "".to_string();
+                    while let Some(&(_, c)) = it.peek() {
+                        match c {
+                            '_' => {
+                                it.next();
+                            }
+                            _ if c.is_digit(radix) => {
+                                digits.push(c);
+                                it.next();
+                            }
+                            _ => break,
+                        }
+                    }
+                    if let Some((_, c)) = it.peek() {
+                        if c.is_ascii_alphanumeric() {
+                            return syntax!(format!("syntax 2"), i);
+                        }
+                    }
+                    match u64::from_str_radix(&digits, radix) {
+                        Ok(num) if num <= MAX_SAFE_INTEGER => {
+                            Token::new(Tok::Number((num as f64).to_string()), i)
+                        }
+                        Ok(_) => return syntax!(format!("literal wider than 53 bits"), i),
+                        Err(_) => return syntax!(format!("syntax 2"), i),
+                    }
+                }
+                '0'..='9' => {
+                    let mut num_string = "".to_string();
+                    num_string.push(c);
+                    let mut exp = false;
+                    while let Some(&(_, c)) = it.peek() {
+                        match c {
+                            '0'..='9' => {
+                                num_string.push(c);
+                                it.next();
+                            }
+                            // `1..5` is a range rather than a number.
+                            '.' if it.clone().nth(1).map(|(_, n)| n) == Some('.') => break,
+                            _ if c == locale.decimal_mark() => {
+                                num_string.push('.');
+                                it.next();
+                            }
+                            '_' => {
+                                it.next();
+                            }
+                            _ if locale.grouping_separators().contains(&c) => {
+                                let group: Vec<bool> = it
+                                    .clone()
+                                    .skip(1)
+                                    .take(4)
+                                    .map(|(_, c)| c.is_ascii_digit())
+                                    .collect();
+                                if !exp && group == [true, true, true, false] {
+                                    it.next();
+                                } else {
+                                    break;
+                                }
+                            }
+                            'e' | 'E' => {
+                                if exp {
+                                    break;
+                                }else{
+                                    exp = true;
+                                    num_string.push(c);
+                                    it.next();
+                                }
+                            }
+                            '−' | '-' => {
+                                if !exp {
+                                    break;
+                                }
+                                num_string.push('-');
+                                it.next();
+                            }
+                            _ => break,
+                        }
+                    }
+                    if let Ok(num) = num_string.parse::<f64>() {
+                        Token::new(Tok::Number(num.to_string()), i)
+                    } else {
+                        return syntax!(format!("syntax 2"), i);
+                    }
+                }
+                'a'..='z' | 'A'..='Z' => {
+                    let mut name = "".to_string();
+                    name.push(c);
+                    while let Some(&(_, c)) = it.peek() {
+                        match c {
+                            'a'..='z' | 'A'..='Z' | '0'..='9' => {
+                                name.push(c);
+                                it.next();
+                            }
+                            _ => break,
+                        }
+                    }
+                    while let Some((_, c)) = it.peek() {
+                        if c.is_whitespace() {
+                            it.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    match name.as_str() {
+                        "and" => Token::new(TOK_AND, i),
+                        "or" => Token::new(TOK_OR, i),
+                        "not" => Token::new(TOK_NOT, i),
+                        "xor" => Token::new(TOK_BIT_XOR, i),
+                        _ => {
+                            if let Some((_, '(')) = it.peek() {
+                                it.next();
+                                Token::new(Tok::Call(name, 1), i)
+                            } else {
+                                Token::new(Tok::Const(name), i)
+                            }
+                        }
+                    }
+                }
+                ' ' | '\t' | '\n' | '\r' => {
+                    continue;
+                }
+                _ => {
+                    return syntax!(format!("syntax 1"), i);
+                }
+            };
+            res.push(token);
+        }
+        Ok(res)
+    }
+}
+
+fn radix(c: Option<&(usize, char)>) -> Option<u32> {
+    match c {
+        Some((_, 'x')) | Some((_, 'X')) => Some(16),
+        Some((_, 'o')) | Some((_, 'O')) => Some(8),
+        Some((_, 'b')) | Some((_, 'B')) => Some(2),
+        _ => None,
+    }
+}
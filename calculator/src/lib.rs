@@ -0,0 +1,1283 @@
+//! Parsing and evaluation of calculator expressions, shared by the web
+//! frontend and the `calc` binary.
+//!
+//! [`eval`] is the quick way in, [`Calculator`] keeps variables, history and
+//! display settings between evaluations.
+
+#[macro_use]
+mod errors;
+#[cfg(feature = "batch")]
+mod batch;
+mod bytecode;
+mod compiled;
+mod complete;
+mod formatter;
+mod highlight;
+mod lexer;
+mod list;
+mod locale;
+mod matrix;
+mod plot;
+mod programmer;
+mod recognize;
+mod rpn;
+mod settings;
+mod simple_code_gen;
+mod token;
+mod value;
+
+use anyhow::Context;
+use anyhow::Result;
+#[cfg(feature = "batch")]
+pub use batch::*;
+use bytecode::*;
+pub use compiled::*;
+pub use complete::*;
+pub use errors::*;
+pub use formatter::*;
+pub use highlight::*;
+pub use lexer::*;
+use list::*;
+pub use locale::*;
+pub use matrix::*;
+pub use plot::*;
+pub use programmer::*;
+pub use recognize::*;
+pub use rpn::*;
+pub use settings::*;
+pub use simple_code_gen::*;
+use std::collections::HashMap;
+pub use std::f64::consts::*;
+pub use std::f64::*;
+pub use token::*;
+pub use value::*;
+
+pub fn eval(expression: &str) -> Result<f64, CalculatorError> {
+    eval_with(expression, &Settings::default())
+}
+
+pub fn eval_with(expression: &str, settings: &Settings) -> Result<f64, CalculatorError> {
+    eval_in(expression, settings, &HashMap::new())
+}
+
+pub fn eval_in(
+    expression: &str,
+    settings: &Settings,
+    variables: &HashMap<String, f64>,
+) -> Result<f64, CalculatorError> {
+    compile(expression, settings, variables)?.eval()
+}
+
+/// Like [`eval`], for expressions that may evaluate to a list.
+pub fn eval_value(expression: &str) -> Result<Value, CalculatorError> {
+    eval_value_in(expression, &Settings::default(), &HashMap::new())
+}
+
+pub fn eval_value_in(
+    expression: &str,
+    settings: &Settings,
+    variables: &HashMap<String, Value>,
+) -> Result<Value, CalculatorError> {
+    let mut simple = compile(expression, settings, &HashMap::new())?;
+    for (name, value) in variables {
+        match value {
+            Value::Number(x) => simple.insert_const(name, *x),
+            Value::List(list) => simple.insert_list(name, list.clone()),
+            Value::Matrix(matrix) => simple.insert_matrix(name, matrix.clone()),
+        }
+    }
+    simple.eval_value()
+}
+
+fn compile(
+    expression: &str,
+    settings: &Settings,
+    variables: &HashMap<String, f64>,
+) -> Result<SimpleCodeGen, CalculatorError> {
+    let tokens = parse_tokens_with(expression.to_string(), settings.locale)?;
+    let mut rpn = RPN::new();
+    rpn.push_all(tokens)?;
+    let mut simple = SimpleCodeGen::new(rpn.output);
+    simple.word_size = settings.word_size;
+    simple.angle = settings.angle;
+    for (name, &value) in variables {
+        simple.insert_const(name, value);
+    }
+    Ok(simple)
+}
+
+/// Splits `x = 1 + 2` into the variable name and the expression.
+fn split_assignment(expression: &str) -> (Option<&str>, &str) {
+    if let Some(i) = expression.find('=') {
+        let name = expression[..i].trim();
+        let rest = &expression[i + 1..];
+        if is_identifier(name) && !rest.starts_with('=') {
+            return (Some(name), rest);
+        }
+    }
+    (None, expression)
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric())
+}
+
+// Operators spelled as words, the lexer never reads them as names.
+const KEYWORDS: [&str; 4] = ["and", "or", "not", "xor"];
+
+/// Whether `name` can hold a variable, typed or from outside such as a
+/// shared link: an identifier that is not a keyword or the name of a
+/// built-in constant or function, which it would hide.
+pub fn is_variable_name(name: &str) -> bool {
+    is_identifier(name)
+        && !KEYWORDS.contains(&name)
+        && !CONSTANTS.iter().any(|&(constant, _)| constant == name)
+        && !FUNCTIONS.iter().any(|&(function, _, _)| function == name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use anyhow::Result;
+    use assert_approx_eq::assert_approx_eq;
+    use std::collections::HashMap;
+
+    macro_rules! eval_test {
+        ($id:ident, $expression:expr, $value:expr) => {
+            #[test]
+            fn $id() -> Result<(), CalculatorError> {
+                assert_approx_eq!(eval($expression)?, $value as f64);
+                Ok(())
+            }
+        };
+        ($id:ident, $expression:expr) => {
+            #[test]
+            fn $id() -> Result<(), CalculatorError> {
+                assert_eq!($expression, true);
+                Ok(())
+            }
+        };
+    }
+
+    eval_test!(eq1, eval("1+").is_err());
+    eval_test!(eq2, eval("=").is_err());
+    eval_test!(eq3, eval("1E1.5").is_err());
+    eval_test!(eq4, eval("NaN")?.is_nan());
+    eval_test!(eq5, eval("Inf")?.is_infinite());
+    eval_test!(eq6, eval("Infinity")?.is_infinite());
+    eval_test!(eq7, eval("-Inf")?.is_infinite());
+    eval_test!(eq8, eval("0/0")?.is_nan());
+    eval_test!(eq9, eval("(2,").is_err());
+
+    eval_test!(ok1, "1", 1);
+    eval_test!(ok2, "π", PI);
+    eval_test!(ok3, "2×3", 6);
+    eval_test!(ok4, "1+√9*2", 7);
+    eval_test!(ok5, "3√4", 6);
+    eval_test!(ok6, "√16*sin(2pi/4)", 4);
+    eval_test!(ok7, "1+1", 2);
+    eval_test!(ok8, "1+-1", 0);
+    eval_test!(ok9, "1+1", 2);
+    eval_test!(ok10, "-0.5", -0.5);
+    eval_test!(ok12, "+1e2", 100);
+    eval_test!(ok13, "1e-1", 0.1);
+    eval_test!(ok14, "1e−1", 0.1);
+    eval_test!(ok15, "-2^3!", -64);
+    eval_test!(ok16, "(-2)^3!", 64);
+    eval_test!(ok17, "-2^1^2", -2);
+    eval_test!(ok18, "--1", 1);
+    eval_test!(ok19, "-3^--2", -9);
+    eval_test!(ok20, "1+2)(2+3", 15);
+    eval_test!(ok21, "1+2)!^-2", 1.0 / 36.0);
+    eval_test!(ok22, "sin(0)", 0);
+    eval_test!(ok23, "cos(0)", 1);
+    eval_test!(ok24, "sin(-1--1)", 0);
+    eval_test!(ok25, "-(2+1)*-(4/2)", 6);
+    eval_test!(ok26, "-0.5E-1", -0.05);
+    eval_test!(ok28, "2 3 4", 24);
+    eval_test!(ok29, "pi", PI);
+    eval_test!(ok30, "e", E);
+    eval_test!(ok36, "5 % 3", 2);
+    eval_test!(ok37, "5.2 % 3.2", 2);
+    eval_test!(ok38, "100.1-100-0.1", 0);
+    eval_test!(ok39, "1.1-1+(-0.1)", 0);
+    eval_test!(ok40, "log(2,8)", 3);
+    eval_test!(ok41, "log(9,81)", 2);
+    eval_test!(ok42, "log(4,2)", 0.5);
+    eval_test!(ok43, "ln(e)", 1);
+    eval_test!(ok44, "log10(10)", 1);
+    eval_test!(ok45, "log10(100)", 2);
+    eval_test!(ok46, "lg(0.1)", -1);
+    eval_test!(ok47, "log2(2)", 1);
+    eval_test!(ok48, "lb(256)", 8);
+    #[cfg(feature = "random")]
+    eval_test!(ok49, "rnd(0,10)*0", 0);
+    eval_test!(ok50, "max(2,3)", 3);
+    eval_test!(ok51, "min(2,3)", 2);
+    eval_test!(ok52, "cbrt(8)", 2);
+    eval_test!(ok53, "cbrt(-8)", -2);
+    eval_test!(ok54, "∛-8", -2);
+    eval_test!(ok55, "1<2", 1);
+    eval_test!(ok56, "2<=1", 0);
+    eval_test!(ok57, "3>2>0", 1);
+    eval_test!(ok58, "1+1==2", 1);
+    eval_test!(ok59, "1!=1", 0);
+    eval_test!(ok60, "3!!=7", 1);
+    eval_test!(ok61, "!0", 1);
+    eval_test!(ok62, "not 1 or 1 and 0", 0);
+    eval_test!(ok63, "1<2 && 2<3", 1);
+    eval_test!(ok64, "0 || 5", 1);
+    eval_test!(ok65, "2>1 ? 10 : 20", 10);
+    eval_test!(ok66, "0 ? 1 : 0 ? 2 : 3", 3);
+    eval_test!(ok67, "1 ? 0 ? 1 : 2 : 3", 2);
+    eval_test!(ok68, "if(2>1, 3, 4)", 3);
+    eval_test!(ok69, "if(0, 3, 4)+1", 5);
+    eval_test!(ok70, "if(1, 2, foo(1))", 2);
+    eval_test!(ok71, "0 && foo(1)", 0);
+    eval_test!(ok72, "1 || foo(1)", 1);
+    eval_test!(ok73, "max(1 ? 2 : 3, 1)", 2);
+    eval_test!(eq10, eval("1 ? 2").is_err());
+    eval_test!(eq11, eval("1 : 2").is_err());
+    eval_test!(eq12, eval("if(1, 2)").is_err());
+    eval_test!(eq13, eval("1 = 2").is_err());
+    eval_test!(eq14, eval("if(0, 2, foo(1))").is_err());
+    eval_test!(eq15, eval("0x").is_err());
+    eval_test!(eq16, eval("0b102").is_err());
+
+    eval_test!(ok74, "0xFF & 0b1010 << 2", 40);
+    eval_test!(ok75, "0x1_0", 16);
+    eval_test!(ok76, "1_000.5", 1000.5);
+    eval_test!(ok77, "0o17 | 0b10000", 31);
+    eval_test!(ok78, "5 xor 3", 6);
+    eval_test!(ok79, "~0", -1);
+    eval_test!(ok80, "1 << 3 + 1", 16);
+    eval_test!(ok81, "-16 >> 2", -4);
+    eval_test!(ok82, "6 & 3 == 2", 0);
+    eval_test!(ok83, "5!", 120);
+    #[cfg(feature = "gamma")]
+    eval_test!(ok84, "0.5!", PI.sqrt() / 2.0);
+    #[cfg(not(feature = "gamma"))]
+    eval_test!(ok84, eval("0.5!").is_err());
+    #[cfg(not(feature = "random"))]
+    eval_test!(ok85, eval("rnd(0,10)").is_err());
+
+    fn word(bits: u32, signed: bool) -> Settings {
+        Settings {
+            word_size: WordSize::new(bits, signed),
+            ..Settings::default()
+        }
+    }
+
+    eval_test!(int1, eval_with("255+1", &word(8, false))? == 0.0);
+    eval_test!(int2, eval_with("127+1", &word(8, true))? == -128.0);
+    eval_test!(int3, eval_with("~0", &word(16, false))? == 65535.0);
+    eval_test!(int4, eval_with("7/2", &word(32, true))? == 3.0);
+    eval_test!(int5, eval_with("1 << 40", &word(32, false))? == 0.0);
+    eval_test!(
+        int6,
+        WordSize::new(8, true).unwrap().format(-1.0, 16).unwrap() == "0xFF"
+    );
+    eval_test!(
+        int7,
+        WordSize::new(16, true).unwrap().format(-1.0, 10).unwrap() == "-1"
+    );
+    eval_test!(
+        int8,
+        WordSize::new(32, false).unwrap().format(10.0, 2).unwrap() == "0b1010"
+    );
+    eval_test!(
+        int9,
+        WordSize::new(32, false).unwrap().format(8.0, 8).unwrap() == "0o10"
+    );
+    eval_test!(int10, WordSize::default().format(35.0, 36).unwrap() == "Z");
+    eval_test!(
+        int11,
+        WordSize::new(0, true).is_none()
+            && WordSize::new(64, true).is_none()
+            && WordSize::new(128, false).is_none()
+            && WordSize::new(12, true).is_none()
+    );
+    eval_test!(
+        int12,
+        [0, 1, 37]
+            .iter()
+            .all(|&radix| WordSize::default().format(5.0, radix).is_none())
+    );
+    eval_test!(
+        int13,
+        matches!(
+            eval("0xFFFFFFFFFFFFFFFF"),
+            Err(CalculatorError::Syntax(_, 0))
+        ) && matches!(eval("0x20000000000000"), Err(CalculatorError::Syntax(_, 0)))
+            && eval("0x1FFFFFFFFFFFFF")? == 9007199254740991.0
+    );
+    eval_test!(
+        int14,
+        eval_with("0xFFFFFFFF", &word(32, true))? == -1.0
+            && eval_with("0xFFFFFFFF", &word(32, false))? == 4294967295.0
+            && eval_with("0x7FFFFFFF + 1", &word(32, true))? == -2147483648.0
+            && eval_with("~0", &word(32, false))? == 4294967295.0
+    );
+    eval_test!(
+        int15,
+        eval("1 << 63")? == 2f64.powi(63) && eval("~0")? == -1.0
+    );
+    eval_test!(int16, {
+        let mut calculator = Calculator::default();
+        calculator.formatter.radix = 16;
+        calculator.set_word_size(WordSize::new(8, true));
+        calculator.expression = "127 + 1".to_string();
+        calculator.eval(0.0)?;
+        let wrapped = calculator.display();
+        calculator.set_word_size(None);
+        calculator.expression = "-255".to_string();
+        calculator.eval(0.0)?;
+        wrapped == "0x80"
+            && calculator.display() == "-0xFF"
+            && calculator.formatter.format(2.5) == "2.5"
+    });
+
+    fn format(notation: Notation, x: f64) -> String {
+        Formatter::new(notation).format(x)
+    }
+
+    eval_test!(fmt1, format(Notation::Auto, eval("0.1+0.2")?) == "0.3");
+    eval_test!(fmt2, format(Notation::Auto, 1e21) == "1e21");
+    eval_test!(fmt3, format(Notation::Auto, -0.000001) == "-0.000001");
+    eval_test!(
+        fmt4,
+        format(Notation::Auto, 1.0 / 3.0) == "0.333333333333333"
+    );
+    eval_test!(fmt5, format(Notation::Fixed(2), PI) == "3.14");
+    eval_test!(fmt6, format(Notation::Fixed(0), 2.5e3) == "2500");
+    eval_test!(fmt7, format(Notation::Scientific, 12345.0) == "1.2345e4");
+    eval_test!(fmt8, format(Notation::Scientific, 0.00012) == "1.2e-4");
+    eval_test!(fmt9, format(Notation::Engineering, 12345.0) == "12.345k");
+    eval_test!(fmt10, format(Notation::Engineering, 0.0047) == "4.7m");
+    eval_test!(fmt11, format(Notation::Engineering, 1e30) == "1e30");
+    eval_test!(fmt12, format(Notation::Significant(3), 2.0) == "2.00");
+    eval_test!(fmt13, format(Notation::Significant(2), 98765.0) == "99000");
+    eval_test!(fmt14, format(Notation::Auto, -1e-20 + 1e-20) == "0");
+    eval_test!(fmt15, format(Notation::Auto, f64::NAN) == "NaN");
+    fn locale(tag: &str) -> Settings {
+        Settings {
+            locale: Locale::from_tag(tag).unwrap(),
+            ..Settings::default()
+        }
+    }
+
+    eval_test!(loc1, eval_with("3.5 + max(1,2)", &locale("en-US"))? == 5.5);
+    eval_test!(loc2, eval_with("1,234", &locale("en-US")).is_err());
+    eval_test!(loc3, eval_with("3,5 + max(1;2)", &locale("de-DE"))? == 5.5);
+    eval_test!(
+        loc4,
+        eval_with("1.234.567,5", &locale("de-DE"))? == 1234567.5
+    );
+    eval_test!(loc5, eval_with("1.5", &locale("de-DE")).is_err());
+    eval_test!(
+        loc6,
+        eval_with("1\u{202f}234,5 + log(2;8)", &locale("fr-FR"))? == 1237.5
+    );
+    eval_test!(
+        loc7,
+        eval_with("1'234.5 + min(1,2)", &locale("de-CH"))? == 1235.5
+    );
+    eval_test!(
+        loc8,
+        Locale::DeDe.formatter(Notation::Fixed(2)).format(-1234.5) == "-1.234,50"
+    );
+    eval_test!(
+        loc9,
+        Locale::FrFr.formatter(Notation::Auto).format(1234.5) == "1\u{202f}234,5"
+    );
+    eval_test!(
+        loc10,
+        Locale::DeCh.formatter(Notation::Auto).format(1e6) == "1'000'000"
+    );
+    eval_test!(
+        loc11,
+        Locale::EnUs.formatter(Notation::Auto).format(1e6) == "1000000"
+    );
+    eval_test!(
+        loc12,
+        LOCALES
+            .iter()
+            .all(|l| Locale::from_tag(l.tag()) == Some(*l))
+    );
+    eval_test!(loc13, eval_with("1 234", &locale("fr-FR"))? == 1234.0);
+    eval_test!(loc14, eval_with("1 234,5", &locale("fr-FR"))? == 1234.5);
+    eval_test!(loc15, eval_with("2 * 1 000 000", &locale("fr-FR"))? == 2e6);
+
+    fn closed_form(x: f64) -> String {
+        recognize(x)
+            .first()
+            .map(|candidate| candidate.expression.clone())
+            .unwrap_or_default()
+    }
+
+    eval_test!(rec1, closed_form(eval("1/3")?) == "1/3");
+    eval_test!(rec2, closed_form(eval("asin(1)")?) == "π/2");
+    eval_test!(rec3, closed_form(eval("-0.75π")?) == "-3π/4");
+    eval_test!(rec4, closed_form(eval("√2/2")?) == "√2/2");
+    eval_test!(rec5, closed_form(PI * PI / 6.0) == "π²/6");
+    eval_test!(rec6, closed_form(2.0 * E) == "2e");
+    eval_test!(rec7, recognize(0.123_456_789_123).is_empty());
+    eval_test!(rec8, recognize(42.0).is_empty());
+    eval_test!(rec9, recognize(0.1 + 0.2)[0].error < 1e-15);
+
+    fn session(expressions: &[&str]) -> Calculator {
+        let mut calculator = Calculator::default();
+        for (i, expression) in expressions.iter().enumerate() {
+            calculator.expression = expression.to_string();
+            let _ = calculator.eval(i as f64);
+        }
+        calculator
+    }
+
+    eval_test!(his1, session(&["2", "ans*3"]).value == 6.0);
+    eval_test!(his2, session(&["2", "3", "1+", "ans+ans1"]).value == 5.0);
+    eval_test!(his3, session(&["1", "2", "3", "ans2"]).value == 1.0);
+    eval_test!(his4, session(&["ans"]).history[0].result.is_err());
+    eval_test!(his5, session(&["1", "1+"]).history[1].timestamp == 1.0);
+    eval_test!(his6, {
+        let mut calculator = session(&["1", "2"]);
+        calculator.remove_entry(1);
+        calculator.answers()["ans"] == 1.0
+    });
+    eval_test!(his7, {
+        let mut calculator = session(&["1", "2"]);
+        calculator.clear_history();
+        calculator.answers().is_empty()
+    });
+
+    eval_test!(err1, eval("1+").unwrap_err().span("1+") == Some(2..2));
+    eval_test!(err2, eval("2 * )").unwrap_err().span("2 * )") == Some(4..5));
+    eval_test!(err3, eval("2 # 3").unwrap_err().span("2 # 3") == Some(2..3));
+    eval_test!(err4, eval("1.2.3").unwrap_err().span("1.2.3") == Some(0..5));
+    eval_test!(err5, eval("foo(1)").unwrap_err().span("foo(1)").is_none());
+    eval_test!(inc1, eval("1+").unwrap_err().is_incomplete("1+"));
+    eval_test!(inc2, eval("max(2, ").unwrap_err().is_incomplete("max(2, "));
+    eval_test!(inc3, eval("1 ?").unwrap_err().is_incomplete("1 ?"));
+    eval_test!(inc4, !eval("2 * )").unwrap_err().is_incomplete("2 * )"));
+    eval_test!(inc5, !eval("foo(1)").unwrap_err().is_incomplete("foo(1)"));
+    eval_test!(pre1, {
+        let mut calculator = session(&["2"]);
+        calculator.expression = "ans+1".to_string();
+        calculator.preview()? == 3.0 && calculator.history.len() == 1
+    });
+    eval_test!(var1, session(&["x = 3", "x^2"]).value == 9.0);
+    eval_test!(var2, session(&["x = 3", "x == 3"]).value == 1.0);
+    eval_test!(
+        var3,
+        session(&["r2d2 = 2", "r2d2 = r2d2 + 1"]).variables["r2d2"] == 3.0
+    );
+    eval_test!(var4, session(&["x = 1+"]).variables.is_empty());
+    eval_test!(
+        var5,
+        session(&["x = 2 * )"]).history[0].result
+            == Err(CalculatorError::Syntax("syntax RParen 1".to_string(), 8))
+    );
+    eval_test!(var6, session(&["2 = 3"]).history[0].result.is_err());
+    eval_test!(
+        var7,
+        is_variable_name("r2")
+            && !is_variable_name("pi")
+            && !is_variable_name("sin")
+            && !is_variable_name("2x")
+            && !is_variable_name("if")
+            && !is_variable_name("and")
+    );
+    eval_test!(var8, {
+        let calculator = session(&[" pi = 3", "pi"]);
+        calculator.history[0].result
+            == Err(CalculatorError::Syntax(
+                "pi is built in and cannot be assigned".to_string(),
+                1,
+            ))
+            && calculator.value == PI
+            && calculator.variables.is_empty()
+    });
+    eval_test!(
+        var9,
+        ["sin = 2", "if = 3", "and = 1", "xor=2"]
+            .iter()
+            .all(|expression| session(&[expression]).history[0].result.is_err())
+    );
+    eval_test!(ang1, {
+        let settings = Settings {
+            angle: Angle::Degrees,
+            ..Settings::default()
+        };
+        (eval_with("sin(30)", &settings)? - 0.5).abs() < 1e-12
+            && (eval_with("acos(0)", &settings)? - 90.0).abs() < 1e-12
+    });
+    eval_test!(
+        stale1,
+        session(&["2", "1+"]).is_stale() && session(&["2", "1+"]).value == 2.0
+    );
+    eval_test!(stale2, !session(&["1+", "2"]).is_stale());
+
+    fn plot(expression: &str) -> Option<Result<Plot, CalculatorError>> {
+        let mut calculator = session(&["a = 2"]);
+        calculator.expression = expression.to_string();
+        calculator.plot()
+    }
+
+    eval_test!(plot1, plot("sin(x)/x").is_none());
+    eval_test!(
+        plot2,
+        plot("plot(sin(x)/x, -10, 10)").unwrap()?
+            == Plot {
+                functions: vec!["sin(x)/x".to_string()],
+                from: -10.0,
+                to: 10.0,
+            }
+    );
+    eval_test!(
+        plot3,
+        plot("plot(a*x, max(x, 1), -pi, 2pi)")
+            .unwrap()?
+            .functions
+            .len()
+            == 2
+    );
+    eval_test!(
+        plot4,
+        plot("plot(x, -1, 1").unwrap()
+            == Err(CalculatorError::Syntax(
+                "plot missing RParen".to_string(),
+                13
+            ))
+    );
+    eval_test!(plot5, plot("plot(x, 1)").unwrap().is_err());
+    eval_test!(
+        plot6,
+        matches!(
+            plot("plot(x+, 0, 1)"),
+            Some(Err(CalculatorError::Syntax(_, 7)))
+        )
+    );
+    eval_test!(plot7, plot("plot(foo(x), 0, 1)").unwrap().is_err());
+    eval_test!(plot8, {
+        let mut graph = Graph::new("x^2", &Settings::default(), &HashMap::new())?;
+        let segments = graph.sample(-1.0, 1.0);
+        segments.len() == 1
+            && segments[0].first() == Some(&(-1.0, 1.0))
+            && segments[0].last() == Some(&(1.0, 1.0))
+    });
+    eval_test!(plot9, {
+        let mut graph = Graph::new("√x", &Settings::default(), &HashMap::new())?;
+        let segments = graph.sample(-1.0, 1.0);
+        segments.len() == 1 && segments[0][0].0 < 0.001
+    });
+    eval_test!(
+        plot10,
+        Graph::new("1/x", &Settings::default(), &HashMap::new())?
+            .sample(-1.0, 1.0)
+            .len()
+            == 2
+    );
+    fn kinds(expression: &str) -> Vec<Highlight> {
+        let mut calculator = session(&["a = 2"]);
+        calculator.expression = expression.to_string();
+        calculator
+            .highlight()
+            .iter()
+            .map(|span| span.highlight)
+            .collect()
+    }
+
+    eval_test!(
+        hl1,
+        kinds("2 * sin(pi)")
+            == [
+                Highlight::Number,
+                Highlight::Operator,
+                Highlight::Function,
+                Highlight::Paren,
+                Highlight::Constant,
+                Highlight::Paren
+            ]
+    );
+    eval_test!(
+        hl2,
+        kinds("a + b + foo(1)")
+            == [
+                Highlight::Variable,
+                Highlight::Operator,
+                Highlight::Unknown,
+                Highlight::Operator,
+                Highlight::Unknown,
+                Highlight::Paren,
+                Highlight::Number,
+                Highlight::Paren
+            ]
+    );
+    eval_test!(
+        hl3,
+        kinds("b = 1 # 2")
+            == [
+                Highlight::Variable,
+                Highlight::Operator,
+                Highlight::Number,
+                Highlight::Invalid
+            ]
+    );
+    eval_test!(
+        hl4,
+        kinds("plot(x, 0, 1)")[..3] == [Highlight::Function, Highlight::Paren, Highlight::Variable]
+    );
+    eval_test!(hl5, {
+        let expression = "max(1, (2)) + 3";
+        let spans = highlight(expression, Locale::EnUs, &HashMap::new());
+        let ranges: Vec<_> = spans.iter().map(|span| span.range.clone()).collect();
+        ranges[..3] == [0..3, 3..4, 4..5] && ranges[ranges.len() - 1] == (14..15)
+    });
+    eval_test!(hl6, {
+        let expression = "max(1, (2)) + 3";
+        let spans = highlight(expression, Locale::EnUs, &HashMap::new());
+        let pair = matching_paren(&spans, expression, 11).unwrap();
+        spans[pair.0].range == (3..4) && spans[pair.1].range == (10..11)
+    });
+    eval_test!(hl7, {
+        let expression = "max(1, (2)) + 3";
+        let spans = highlight(expression, Locale::EnUs, &HashMap::new());
+        let pair = matching_paren(&spans, expression, 7).unwrap();
+        spans[pair.0].range == (7..8) && spans[pair.1].range == (9..10)
+    });
+    eval_test!(
+        hl8,
+        matching_paren(&highlight("(1", Locale::EnUs, &HashMap::new()), "(1", 1).is_none()
+    );
+    fn names(expression: &str, cursor: usize) -> Vec<String> {
+        let mut calculator = session(&["alpha = 2"]);
+        calculator.expression = expression.to_string();
+        calculator
+            .complete(cursor)
+            .into_iter()
+            .map(|completion| completion.name)
+            .collect()
+    }
+
+    eval_test!(
+        com1,
+        names("1 + l", 5) == ["lb", "lg", "ln", "len", "log", "log2", "log10"]
+    );
+    eval_test!(com2, names("2a", 2) == ["ans", "acos", "asin", "alpha"]);
+    eval_test!(com3, names("sin", 3) == ["sin"]);
+    eval_test!(
+        com4,
+        names("pi", 2).is_empty() && names("1 + ", 4).is_empty() && names("12", 2).is_empty()
+    );
+    eval_test!(com5, names("na", 2) == ["NaN"]);
+    eval_test!(com6, {
+        let completions = complete("max(co(1))", 6, &HashMap::new());
+        completions.len() == 1 && completions[0].range == (4..6) && completions[0].text() == "cos("
+    });
+    eval_test!(com7, {
+        let builtins = SimpleCodeGen::new(vec![]);
+        complete("", 0, &HashMap::new()).is_empty()
+            && "abcdefghijklmnopqrstuvwxyz".chars().all(|c| {
+                complete(&c.to_string(), 1, &HashMap::new())
+                    .iter()
+                    .all(|completion| match completion.kind {
+                        CompletionKind::Function => {
+                            builtins.has_function(&completion.name)
+                                || ["if", "plot"].contains(&completion.name.as_str())
+                        }
+                        _ => builtins.has_const(&completion.name),
+                    })
+            })
+    });
+    eval_test!(
+        plot11,
+        Graph::new("x < 0.3", &Settings::default(), &HashMap::new())?
+            .sample(0.0, 1.0)
+            .len()
+            == 2
+    );
+    fn compiled(expression: &str, variables: &[&str]) -> Result<CompiledExpr, CalculatorError> {
+        let constants = [("a".to_string(), 2.0), ("x".to_string(), 100.0)]
+            .iter()
+            .cloned()
+            .collect();
+        CompiledExpr::new(expression, variables, &Settings::default(), &constants)
+    }
+
+    eval_test!(cmp1, {
+        let mut expr = compiled("x^2 + a*y", &["x", "y"])?;
+        expr.eval(&[3.0, 1.0])? == 11.0 && expr.eval(&[-1.0, 0.5])? == 2.0
+    });
+    eval_test!(
+        cmp2,
+        compiled("x > 0 ? sqrt(x) : -1", &["x"])?.eval(&[-4.0])? == -1.0
+    );
+    eval_test!(
+        cmp3,
+        compiled("x", &[])?.eval(&[])? == 100.0 && compiled("pi", &["pi"])?.eval(&[3.0])? == 3.0
+    );
+    eval_test!(
+        cmp4,
+        compiled("x +", &["x"]).is_err() && compiled("x", &["x"])?.eval(&[]).is_err()
+    );
+    eval_test!(cmp5, {
+        let mut expr = compiled("x < 1 || foo(x)", &["x"])?;
+        expr.eval(&[0.0])? == 1.0
+            && expr.eval(&[2.0]) == Err(CalculatorError::Runtime("function not found".to_string()))
+    });
+    eval_test!(cmp6, {
+        let settings = Settings {
+            word_size: WordSize::new(8, true),
+            ..Settings::default()
+        };
+        CompiledExpr::new("x + 1", &["x"], &settings, &HashMap::new())?.eval(&[127.0])? == -128.0
+    });
+    eval_test!(vm1, std::mem::size_of::<Instr>() <= 8);
+    eval_test!(vm2, "0 ? 2 : 1 ? 3 : 4", 3);
+    eval_test!(vm3, "if(1 < 2, 3, 4) + if(0, 5, 6)", 9);
+    eval_test!(vm4, "max(1 > 0 && 2, 0 || 0) + min(e, 2)", 3);
+    eval_test!(
+        vm5,
+        eval("max(1)")
+            == Err(CalculatorError::Runtime(
+                "wrong no of arguments".to_string()
+            ))
+            && eval("0 ? max(1) : 2")? == 2.0
+    );
+    eval_test!(vm6, {
+        let mut expr = compiled("(x > 0 ? x : -x) * (y || 2)", &["x", "y"])?;
+        expr.eval(&[-3.0, 0.0])? == 3.0 && expr.eval(&[4.0, 5.0])? == 4.0
+    });
+
+    // Whether `eval_columns` gives what `eval` gives row by row, over more
+    // rows than go through an instruction at a time.
+    fn same_columns(expression: &str, settings: &Settings) -> Result<bool, CalculatorError> {
+        let mut expr = CompiledExpr::new(expression, &["x", "y"], settings, &HashMap::new())?;
+        let x: Vec<f64> = (0..1000).map(|i| i as f64 / 10.0 - 50.0).collect();
+        let y: Vec<f64> = x.iter().map(|x| x * x - 3.0).collect();
+        let columns = expr.eval_columns(&[&x, &y])?;
+        let mut same = columns.len() == x.len();
+        for (i, column) in columns.iter().enumerate() {
+            let row = expr.eval(&[x[i], y[i]])?;
+            same &= row.to_bits() == column.to_bits() || row.is_nan() && column.is_nan();
+        }
+        Ok(same)
+    }
+
+    eval_test!(
+        col1,
+        same_columns(
+            "x^3 - 2y + sin(x)/x + max(x, 0.5) - sqrt(y)",
+            &Settings::default()
+        )?
+    );
+    eval_test!(
+        col2,
+        same_columns(
+            "x > 20 ? y : x < -20 ? -x : log(2, y)",
+            &Settings::default()
+        )?
+    );
+    eval_test!(
+        col3,
+        same_columns("(x && y) + (x || 1) + !x + 3!", &Settings::default())?
+    );
+    eval_test!(col4, same_columns("x * y + 100 << 1 ^ ~x", &word(8, true))?);
+    eval_test!(col5, {
+        let expr = compiled("x + a", &["x"])?;
+        expr.eval_columns(&[&[]])?.is_empty() && compiled("1", &[])?.eval_columns(&[])?.is_empty()
+    });
+    eval_test!(col6, {
+        let expr = compiled("x > 1 ? nosuch(x) : x", &["x", "y"])?;
+        expr.eval_columns(&[&[1.0, 0.0], &[1.0, 2.0]])? == [1.0, 0.0]
+            && expr.eval_columns(&[&[1.0, 2.0], &[1.0, 2.0]]).is_err()
+            && expr.eval_columns(&[&[1.0], &[1.0, 2.0]]).is_err()
+            && expr.eval_columns(&[&[1.0]]).is_err()
+    });
+    #[cfg(feature = "batch")]
+    fn batch(
+        input: &str,
+        expressions: &[(&str, &str)],
+    ) -> Result<(String, Vec<RowError>), BatchError> {
+        let mut output = Vec::new();
+        let errors = eval_csv(
+            input.as_bytes(),
+            &mut output,
+            expressions,
+            &Settings::default(),
+            &HashMap::new(),
+        )?;
+        Ok((String::from_utf8(output).unwrap(), errors))
+    }
+    #[cfg(feature = "batch")]
+    eval_test!(csv1, {
+        let (output, errors) = batch(
+            "price,qty\n2,3\n1.5, 4\n",
+            &[("total", "price qty"), ("half", "price/2")],
+        )
+        .unwrap();
+        output == "price,qty,total,half\n2,3,6,1\n1.5, 4,6,0.75\n" && errors.is_empty()
+    });
+    #[cfg(feature = "batch")]
+    eval_test!(csv2, {
+        let (output, errors) = batch(
+            "name,x\na,4\nb,-1\nc,\n",
+            &[("root", "sqrt(x)"), ("bad", "nosuch(x)")],
+        )
+        .unwrap();
+        output == "name,x,root,bad\na,4,2,\nb,-1,NaN,\nc,,,\n"
+            && errors.len() == 4
+            && errors[0]
+                == RowError {
+                    row: 1,
+                    column: "bad".to_string(),
+                    error: CalculatorError::Runtime("function not found".to_string()),
+                }
+            && errors[2].row == 3
+            && errors[2].error == CalculatorError::Runtime("x is not a number: \"\"".to_string())
+    });
+    #[cfg(feature = "batch")]
+    eval_test!(csv3, {
+        matches!(
+            batch("x\n1\n", &[("y", "x +")]),
+            Err(BatchError::Expression { ref name, error: CalculatorError::Syntax(_, 3) }) if name == "y"
+        )
+    });
+    #[cfg(feature = "batch")]
+    eval_test!(csv4, {
+        let (output, errors) = batch("x,y\n1,2\n3\n4,5,6\n7,8\n", &[("s", "x + y")]).unwrap();
+        output == "x,y,s\n1,2,3\n3,,\n4,5,\n7,8,15\n"
+            && errors.len() == 2
+            && errors[0]
+                == RowError {
+                    row: 2,
+                    column: "s".to_string(),
+                    error: CalculatorError::Runtime(
+                        "the row has 1 fields, the header 2".to_string(),
+                    ),
+                }
+            && errors[1].row == 3
+    });
+    #[cfg(feature = "batch")]
+    eval_test!(csv5, {
+        matches!(batch("x,y\n1,2\n", &[("y", "2x")]), Err(BatchError::DuplicateColumn(ref name)) if name == "y")
+            && matches!(batch("x\n1\n", &[("a", "x"), ("a", "2x")]), Err(BatchError::DuplicateColumn(ref name)) if name == "a")
+    });
+    #[cfg(feature = "batch")]
+    eval_test!(csv6, {
+        matches!(batch("x\n1\n", &[("y", "x"), (" y", "2x")]), Err(BatchError::DuplicateColumn(ref name)) if name == " y")
+            && matches!(batch("x, x\n1,2\n", &[("y", "x")]), Err(BatchError::DuplicateHeader(ref name)) if name == "x")
+    });
+
+    fn list(expression: &str) -> Result<Vec<f64>, CalculatorError> {
+        match eval_value(expression)? {
+            Value::List(list) => Ok(list),
+            other => runtime!(format!("expected a list, got a {}", other.kind())),
+        }
+    }
+
+    eval_test!(ls1, list("[1, 2, 3] * 2")? == [2.0, 4.0, 6.0]);
+    eval_test!(ls2, "sum([1, 2, 3])", 6);
+    eval_test!(
+        ls3,
+        "prod(1..5) + mean([1, 2, 3, 4]) + len(1..10) + sum(1..10)",
+        187.5
+    );
+    eval_test!(
+        ls4,
+        list("[1, 2] + [3, 4]")? == [4.0, 6.0] && eval("sum([1, 2] + [1])").is_err()
+    );
+    eval_test!(
+        ls5,
+        list("-[1, -2]")? == [-1.0, 2.0] && list("[1, 2, 3] > 1")? == [0.0, 1.0, 1.0]
+    );
+    eval_test!(
+        ls6,
+        list("sqrt([4, 9])")? == [2.0, 3.0] && list("max([1, 5], 3)")? == [3.0, 5.0]
+    );
+    eval_test!(
+        ls7,
+        list("2^[1, 2]")? == [2.0, 4.0] && list("[1, 2] % 2 == 0")? == [0.0, 1.0]
+    );
+    eval_test!(ls8, "sum([1, 2, 3]!)", 9);
+    eval_test!(ls9, "[4, 5, 6][2] + (1..10)[10]", 15);
+    eval_test!(
+        ls10,
+        list("(1..10)[[2, 3]]")? == [2.0, 3.0]
+            && eval("[1, 2][3]").is_err()
+            && eval("2[1]").is_err()
+    );
+    eval_test!(
+        ls11,
+        list("[]")?.is_empty() && eval("sum([])")? == 0.0 && eval("len([])")? == 0.0
+    );
+    eval_test!(
+        ls12,
+        list("1.5..3")? == [1.5, 2.5]
+            && list("3..1")?.is_empty()
+            && list("1..2+1")? == [1.0, 2.0, 3.0]
+    );
+    eval_test!(
+        ls13,
+        eval("[[1], 2]").is_err() && eval("[1] ? 1 : 2").is_err() && eval("1..1e9").is_err()
+    );
+    eval_test!(
+        ls14,
+        list("0 ? [1] : [2, 3]")? == [2.0, 3.0] && eval("if(1, 2, [3])")? == 2.0
+    );
+    eval_test!(
+        ls15,
+        eval("[1, 2]")
+            == Err(CalculatorError::Runtime(
+                "expected a number, got a list".to_string()
+            ))
+    );
+    eval_test!(
+        ls16,
+        compiled("sum([x, 1])", &["x"]).is_err()
+            && compiled("sum(x)", &["x"])?.eval(&[3.0])? == 3.0
+    );
+    eval_test!(
+        ls17,
+        eval("[1, 2").is_err() && eval("[1, ]").is_err() && eval("(1, 2]").is_err()
+    );
+    eval_test!(ls18, {
+        let settings = Settings {
+            locale: Locale::DeDe,
+            ..Settings::default()
+        };
+        eval_value_in("[1,5; 2]", &settings, &HashMap::new())? == Value::List(vec![1.5, 2.0])
+    });
+    eval_test!(ls19, {
+        let mut variables = HashMap::new();
+        variables.insert("v".to_string(), Value::List(vec![1.0, 2.0]));
+        variables.insert("x".to_string(), Value::Number(3.0));
+        eval_value_in("v x", &Settings::default(), &variables)? == Value::List(vec![3.0, 6.0])
+    });
+    eval_test!(ls20, {
+        let mut calculator = Calculator::default();
+        let mut run = |expression: &str| {
+            calculator.expression = expression.to_string();
+            calculator.eval(0.0)
+        };
+        run("v = [1, 2, 3]")?;
+        run("v[1] + sum(v)")? == Value::Number(7.0)
+            && run("v * 2")? == Value::List(vec![2.0, 4.0, 6.0])
+            && run("ans[3]")? == Value::Number(6.0)
+            && run("v = 1")? == Value::Number(1.0)
+            && calculator.lists.is_empty()
+            && calculator.variables["v"] == 1.0
+    });
+    eval_test!(ls21, {
+        let formatter = Formatter {
+            decimal_mark: ',',
+            ..Formatter::default()
+        };
+        let value: Value = "[1, 2.5]".parse().unwrap();
+        formatter.format_value(&value) == "[1; 2,5]" && value.to_string() == "[1, 2.5]"
+    });
+    eval_test!(ls22, {
+        let condition = |expression| {
+            eval_value(expression)
+                == Err(CalculatorError::Runtime(
+                    "a condition must be a number, not a list".to_string(),
+                ))
+        };
+        condition("[1, 2] && 1")
+            && condition("[0, 1] || 1")
+            && condition("[1, 0] ? 2 : 3")
+            && condition("if([1, 0], 2, 3)")
+            && list("not [1, 0]")? == [0.0, 1.0]
+            && list("1 && [1, 2]")? == [1.0, 1.0]
+            && eval("1 || [1, 2]")? == 1.0
+    });
+
+    // The elements of a matrix row after row, checked against `expected`
+    // with some room for rounding.
+    fn matrix(expression: &str, expected: &[f64]) -> Result<bool, CalculatorError> {
+        let elements = match eval_value(expression)? {
+            Value::Matrix(matrix) => matrix.into_elements(),
+            Value::List(list) => list,
+            other => return runtime!(format!("expected a matrix, got a {}", other.kind())),
+        };
+        let close = elements
+            .iter()
+            .zip(expected)
+            .all(|(a, b)| (a - b).abs() < 1e-9);
+        Ok(elements.len() == expected.len() && close)
+    }
+
+    eval_test!(
+        mx1,
+        matrix(
+            "[[1, 2], [3, 4]] * [[5, 6], [7, 8]]",
+            &[19.0, 22.0, 43.0, 50.0]
+        )?
+    );
+    eval_test!(
+        mx2,
+        matrix(
+            "[[1, 2], [3, 4]] + [[1, 1], [1, 1]] * 2",
+            &[3.0, 4.0, 5.0, 6.0]
+        )?
+    );
+    eval_test!(
+        mx3,
+        matrix("[[1, 2, 3], [4, 5, 6]] * [1, 0, 1]", &[4.0, 10.0])?
+    );
+    eval_test!(mx4, matrix("[1, 1] * [[1, 2], [3, 4]]", &[4.0, 6.0])?);
+    eval_test!(mx5, "det([[1, 2], [3, 4]])", -2);
+    eval_test!(mx6, "det([[1, 2, 0], [4, 0, 1], [0, 1, 2]])", -17);
+    eval_test!(
+        mx7,
+        matrix("inv([[4, 7], [2, 6]])", &[0.6, -0.7, -0.2, 0.4])?
+    );
+    eval_test!(
+        mx8,
+        matrix(
+            "transpose([[1, 2, 3], [4, 5, 6]])",
+            &[1.0, 4.0, 2.0, 5.0, 3.0, 6.0]
+        )?
+    );
+    eval_test!(
+        mx9,
+        "rank([[1, 2], [2, 4]]) + rank([[1, 0], [0, 1]]) * 10",
+        21
+    );
+    eval_test!(
+        mx10,
+        matrix("solve([[2, 1], [1, 3]], [3, 5])", &[0.8, 1.4])?
+    );
+    eval_test!(
+        mx11,
+        matrix("eig([[2, 0, 0], [0, 3, 4], [0, 4, 9]])", &[1.0, 2.0, 11.0])?
+    );
+    eval_test!(mx12, matrix("eig([[4, 1], [2, 3]])", &[2.0, 5.0])?);
+    eval_test!(
+        mx13,
+        matrix("[[1, 1], [0, 1]]^3", &[1.0, 3.0, 0.0, 1.0])?
+            && matrix("[[2, 0], [0, 4]]^-1", &[0.5, 0.0, 0.0, 0.25])?
+    );
+    eval_test!(
+        mx14,
+        matrix("[[1, 2], [3, 4]][2]", &[3.0, 4.0])?
+            && eval("[[1, 2], [3, 4]][2][1] + sum([[1, 2], [3, 4]])")? == 13.0
+    );
+    eval_test!(mx15, {
+        let runtime =
+            |expression: &str| matches!(eval_value(expression), Err(CalculatorError::Runtime(_)));
+        runtime("[[1, 2], [3, 4]] * [[1, 2, 3]]")
+            && runtime("[[1, 2]] + [[1, 2], [3, 4]]")
+            && runtime("[[1, 2], [3]]")
+            && runtime("det([[1, 2, 3], [4, 5, 6]])")
+            && runtime("inv([[1, 2], [2, 4]])")
+            && runtime("solve([[1, 0], [0, 1]], [1, 2, 3])")
+            && runtime("eig([[0, -1], [1, 0]])")
+            && runtime("[[1, 2], [3, 4]] + [1, 2]")
+    });
+    eval_test!(mx16, "det(4) + inv(4) + solve(2, 6) + rank(0)", 7.25);
+    eval_test!(
+        mx18,
+        "rank([[1, 2], [2, 4.0000000001]]) + rank([[1e-20, 0], [0, 1e-20]]) * 10",
+        21
+    );
+    eval_test!(mx19, {
+        eval_value("eig([[1e308, 1e308], [1e308, 1e308]])")?
+            == Value::List(vec![0.0, f64::INFINITY])
+            && matrix("eig([[0, 0], [0, 0]])", &[0.0, 0.0])?
+            && matrix("eig([[1e-300, 0], [0, 3e-300]]) * 1e300", &[1.0, 3.0])?
+            && Matrix::from_rows(vec![vec![1.0, 0.0], vec![0.0, f64::INFINITY]])?
+                .eig()
+                .is_err()
+    });
+    eval_test!(mx17, {
+        let mut calculator = Calculator::default();
+        let mut run = |expression: &str| {
+            calculator.expression = expression.to_string();
+            calculator.eval(0.0)
+        };
+        run("A = [[1, 2], [3, 4]]")?;
+        let value: Value = "[[1, 2.5], [3, 4]]".parse()?;
+        run("det(A * transpose(A))")?.number()?.round() == 4.0
+            && run("A[1] * A")? == Value::List(vec![7.0, 10.0])
+            && calculator.formatter.format_value(&calculator.lists["A"]) == "[[1, 2], [3, 4]]"
+            && value.to_string() == "[[1, 2.5], [3, 4]]"
+            && "[[1, 2], [3]]".parse::<Value>().is_err()
+    });
+
+    eval_test!(
+        fmt16,
+        Formatter {
+            thousands_separator: Some('.'),
+            decimal_mark: ',',
+            ..Formatter::new(Notation::Fixed(2))
+        }
+        .format(-1234567.891)
+            == "-1.234.567,89"
+    );
+}
+
+#[derive(Default)]
+pub struct Calculator {
+    pub expression: String,
+    pub value: Value,
+    pub formatter: Formatter,
+    pub settings: Settings,
+    pub recognize: bool,
+    pub history: Vec<Entry>,
+    pub variables: HashMap<String, f64>,
+    /// Variables holding lists or matrices, apart so that `variables` stays
+    /// the numbers that sessions and links save.
+    pub lists: HashMap<String, Value>,
+    /// Outcome of the last evaluation. `value` keeps the last valid result.
+    pub result: Option<Result<Value, CalculatorError>>,
+}
+
+pub struct Entry {
+    pub expression: String,
+    pub result: Result<Value, CalculatorError>,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: f64,
+}
+
+impl Calculator {
+    /// Sets the word results wrap around, which the formatter also shows
+    /// negative numbers of another radix in.
+    pub fn set_word_size(&mut self, word_size: Option<WordSize>) {
+        self.settings.word_size = word_size;
+        self.formatter.word_size = word_size;
+    }
+
+    pub fn display(&self) -> String {
+        self.formatter.format_value(&self.value)
+    }
+
+    /// Evaluates `expression` without touching the history or variables.
+    pub fn preview(&self) -> Result<Value, CalculatorError> {
+        let (name, expression) = split_assignment(&self.expression);
+        if let Some(name) = name.filter(|name| !is_variable_name(name)) {
+            let position = self.expression.len() - self.expression.trim_start().len();
+            return syntax!(
+                format!("{} is built in and cannot be assigned", name),
+                position
+            );
+        }
+        eval_value_in(expression, &self.settings, &self.scope())
+            .map_err(|error| error.offset(self.expression.len() - expression.len()))
+    }
+
+    /// Reads `expression` as a `plot(...)` call, `None` if it is not one.
+    pub fn plot(&self) -> Option<Result<Plot, CalculatorError>> {
+        Plot::parse(&self.expression, &self.settings, &self.numbers())
+    }
+
+    /// Highlighted spans of `expression`, see [`highlight`].
+    pub fn highlight(&self) -> Vec<Span> {
+        // Only the names matter, a list can go in as any number.
+        let scope = self.scope().into_iter();
+        let names = scope
+            .map(|(name, value)| (name, value.as_number().unwrap_or(f64::NAN)))
+            .collect();
+        highlight(&self.expression, self.settings.locale, &names)
+    }
+
+    /// Names that complete the one being typed at the byte offset `cursor`.
+    pub fn complete(&self, cursor: usize) -> Vec<Completion> {
+        complete(&self.expression, cursor, &self.numbers())
+    }
+
+    /// Compiles one of the functions of a plot with the current variables.
+    pub fn graph(&self, function: &str) -> Result<Graph, CalculatorError> {
+        Graph::new(function, &self.settings, &self.numbers())
+    }
+
+    // Names an expression can refer to besides the constants.
+    fn scope(&self) -> HashMap<String, Value> {
+        let mut scope = self.answer_values();
+        scope.extend(
+            self.variables
+                .iter()
+                .map(|(name, &value)| (name.clone(), Value::Number(value))),
+        );
+        scope.extend(self.lists.clone());
+        scope
+    }
+
+    // The names in `scope` that are numbers, for plots and completions.
+    fn numbers(&self) -> HashMap<String, f64> {
+        let scope = self.scope().into_iter();
+        scope
+            .filter_map(|(name, value)| Some((name, value.as_number()?)))
+            .collect()
+    }
+
+    /// Evaluates `expression` and records it in the history. `name = ...`
+    /// also stores the result as a variable.
+    pub fn eval(&mut self, timestamp: f64) -> Result<Value, CalculatorError> {
+        let result = self.preview();
+        if let Ok(value) = &result {
+            self.value = value.clone();
+            if let (Some(name), _) = split_assignment(&self.expression) {
+                match value {
+                    Value::Number(x) => {
+                        self.lists.remove(name);
+                        self.variables.insert(name.to_string(), *x);
+                    }
+                    value => {
+                        self.variables.remove(name);
+                        self.lists.insert(name.to_string(), value.clone());
+                    }
+                }
+            }
+        }
+        self.result = Some(result.clone());
+        self.history.push(Entry {
+            expression: self.expression.clone(),
+            result: result.clone(),
+            timestamp,
+        });
+        result
+    }
+
+    pub fn is_stale(&self) -> bool {
+        matches!(self.result, Some(Err(_)))
+    }
+
+    /// `ans` is the last successful result, `ans1` the one before it and so
+    /// on. Results that are lists or matrices are left out.
+    pub fn answers(&self) -> HashMap<String, f64> {
+        let answers = self.answer_values().into_iter();
+        answers
+            .filter_map(|(name, value)| Some((name, value.as_number()?)))
+            .collect()
+    }
+
+    fn answer_values(&self) -> HashMap<String, Value> {
+        let mut answers = HashMap::new();
+        let values = self
+            .history
+            .iter()
+            .rev()
+            .filter_map(|entry| entry.result.clone().ok());
+        for (i, value) in values.enumerate() {
+            if i == 0 {
+                answers.insert("ans".to_string(), value);
+            } else {
+                answers.insert(format!("ans{}", i), value);
+            }
+        }
+        answers
+    }
+
+    pub fn remove_entry(&mut self, index: usize) {
+        if index < self.history.len() {
+            self.history.remove(index);
+        }
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+}
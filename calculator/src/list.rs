@@ -0,0 +1,234 @@
+use super::*;
+use std::collections::HashMap;
+
+// Most elements a range makes, so that `1..1e12` fails instead of filling
+// the memory.
+const MAX_RANGE: f64 = 1e7;
+
+/// Evaluates `rpn` with values that may be lists or matrices, where `lists`
+/// hide `consts` of the same name. Operators and functions go over the
+/// elements of a list, pairing them up between two lists of the same length,
+/// and the same for matrices, except that `*` and `^` multiply them. The
+/// conditions of `&&`, `||`, `?:` and `if` are jumps and must be numbers.
+/// The register machine does the expressions without lists.
+pub(crate) fn eval_list(
+    rpn: &[Tok],
+    consts: &HashMap<String, f64>,
+    lists: &HashMap<String, Value>,
+    functions: &HashMap<String, Function>,
+    word_size: Option<WordSize>,
+    angle: Angle,
+) -> Result<Value, CalculatorError> {
+    let wrap = |x: f64| word_size.map_or(x, |word_size| word_size.wrap(x));
+    let mut stack: Vec<Value> = vec![];
+    let mut pc = 0;
+    while let Some(tok) = rpn.get(pc) {
+        pc += 1;
+        let value = match tok {
+            Tok::Number(number) => match number.parse() {
+                Ok(x) => Value::Number(wrap(x)),
+                Err(_) => return runtime!(format!("parse number")),
+            },
+            Tok::Const(name) => match (lists.get(name), consts.get(name)) {
+                (Some(value), _) => value.clone().try_map(|x| Ok(wrap(x)))?,
+                (None, Some(&x)) => Value::Number(wrap(x)),
+                (None, None) => return runtime!(Failure::UnknownName.message().to_string()),
+            },
+            &TOK_RANGE => {
+                let to = number(stack.pop().unwrap(), "the end of a range")?;
+                let from = number(stack.pop().unwrap(), "the start of a range")?;
+                range(from, to)?.try_map(|x| Ok(wrap(x)))?
+            }
+            Tok::Operator(op) if is_unary(op) => stack
+                .pop()
+                .unwrap()
+                .try_map(|x| Ok(wrap(unary(*op, x, word_size))))?,
+            Tok::Operator(op) => {
+                let b = stack.pop().unwrap();
+                let a = stack.pop().unwrap();
+                match (op, a, b) {
+                    (Op::Mul, a, b) if is_product(&a, &b) => {
+                        product(a, b)?.try_map(|x| Ok(wrap(x)))?
+                    }
+                    (Op::Power, Value::Matrix(a), b) => Value::Matrix(
+                        a.pow(number(b, "the power of a matrix")?)?
+                            .try_map(|x| Ok(wrap(x)))?,
+                    ),
+                    (_, a, b) => a.try_zip(b, |a, b| Ok(wrap(binary(*op, a, b, word_size))))?,
+                }
+            }
+            Tok::Factorial => stack.pop().unwrap().try_map(|x| Ok(wrap(factorial(x)?)))?,
+            Tok::Call(name, arity) => match functions.get(name) {
+                Some(function) if function.arity() == *arity => {
+                    let args = stack.split_off(stack.len() - arity);
+                    call(*function, args, angle)?.try_map(|x| Ok(wrap(x)))?
+                }
+                Some(_) => return runtime!(Failure::Arity.message().to_string()),
+                None => return runtime!(Failure::UnknownFunction.message().to_string()),
+            },
+            Tok::List(length) => {
+                let elements = stack.split_off(stack.len() - length);
+                if let Some(Value::List(_)) = elements.first() {
+                    let rows = elements.into_iter().map(|row| match row {
+                        Value::List(row) => Ok(row),
+                        _ => runtime!(format!("a row of a matrix must be a list")),
+                    });
+                    Value::Matrix(Matrix::from_rows(rows.collect::<Result<_, _>>()?)?)
+                } else {
+                    let elements = elements
+                        .into_iter()
+                        .map(|element| number(element, "an element of a list"));
+                    Value::List(elements.collect::<Result<_, _>>()?)
+                }
+            }
+            Tok::Index => {
+                let index = stack.pop().unwrap();
+                match stack.pop().unwrap() {
+                    Value::List(list) => index.try_map(|i| element(&list, i))?,
+                    Value::Matrix(matrix) => row(&matrix, index)?,
+                    Value::Number(_) => return runtime!(format!("only lists have elements")),
+                }
+            }
+            Tok::Jump(target) => {
+                pc = *target;
+                continue;
+            }
+            Tok::JumpUnless(target) => {
+                if !truthy(number(stack.pop().unwrap(), "a condition")?) {
+                    pc = *target;
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        stack.push(value);
+    }
+    match stack.pop() {
+        Some(value) => Ok(value),
+        None => runtime!(format!("empty expression")),
+    }
+}
+
+// The number in `value`, `what` tells where a list cannot go.
+fn number(value: Value, what: &str) -> Result<f64, CalculatorError> {
+    match value {
+        Value::Number(x) => Ok(x),
+        other => runtime!(format!("{} must be a number, not a {}", what, other.kind())),
+    }
+}
+
+// Whether `a * b` is a matrix product rather than going over the elements:
+// a matrix times a matrix or a list, or a list times a matrix.
+fn is_product(a: &Value, b: &Value) -> bool {
+    matches!(
+        (a, b),
+        (Value::Matrix(_), Value::Matrix(_))
+            | (Value::Matrix(_), Value::List(_))
+            | (Value::List(_), Value::Matrix(_))
+    )
+}
+
+// A list stands for a column on the right and for a row on the left.
+fn product(a: Value, b: Value) -> Result<Value, CalculatorError> {
+    Ok(match (a, b) {
+        (Value::Matrix(a), Value::Matrix(b)) => Value::Matrix(a.mul(&b)?),
+        (Value::Matrix(a), Value::List(b)) => Value::List(a.mul_vector(b)?),
+        (Value::List(a), Value::Matrix(b)) => Value::List(b.transpose().mul_vector(a)?),
+        _ => unreachable!(),
+    })
+}
+
+fn call(function: Function, mut args: Vec<Value>, angle: Angle) -> Result<Value, CalculatorError> {
+    if function.is_matrix() && args.iter().any(|arg| !matches!(arg, Value::Number(_))) {
+        return call_matrix(function, args);
+    }
+    if function.is_aggregate() {
+        let list = match args.pop().unwrap() {
+            Value::List(list) => list,
+            Value::Matrix(matrix) if matches!(function, Function::Len) => vec![0.0; matrix.rows()],
+            Value::Matrix(matrix) => matrix.into_elements(),
+            Value::Number(x) => vec![x],
+        };
+        let z = match function {
+            Function::Sum => list.iter().sum(),
+            Function::Prod => list.iter().product(),
+            Function::Mean => list.iter().sum::<f64>() / list.len() as f64,
+            _ => list.len() as f64,
+        };
+        return Ok(Value::Number(z));
+    }
+    let x = args.pop().unwrap();
+    match args.pop() {
+        Some(y) => y.try_zip(x, |y, x| function.apply(&[y, x], angle)),
+        None => x.try_map(|x| function.apply(&[x], angle)),
+    }
+}
+
+// The functions of linear algebra, with a list as the right side of `solve`.
+fn call_matrix(function: Function, mut args: Vec<Value>) -> Result<Value, CalculatorError> {
+    let x = args.pop().unwrap();
+    if let Function::Solve = function {
+        let a = matrix(args.pop().unwrap())?;
+        return Ok(match x {
+            Value::List(b) => Value::List(a.solve(&Matrix::column(b))?.into_elements()),
+            b => Value::Matrix(a.solve(&matrix(b)?)?),
+        });
+    }
+    let x = matrix(x)?;
+    Ok(match function {
+        Function::Det => Value::Number(x.det()?),
+        Function::Inv => Value::Matrix(x.inv()?),
+        Function::Transpose => Value::Matrix(x.transpose()),
+        Function::Rank => Value::Number(x.rank() as f64),
+        _ => Value::List(x.eig()?),
+    })
+}
+
+// The matrix in `value`, a number is one of 1×1.
+fn matrix(value: Value) -> Result<Matrix, CalculatorError> {
+    match value {
+        Value::Matrix(matrix) => Ok(matrix),
+        Value::Number(x) => Ok(Matrix::column(vec![x])),
+        Value::List(_) => runtime!(format!("expected a matrix, got a list")),
+    }
+}
+
+// `from`, `from + 1` and so on up to `to`, both included.
+fn range(from: f64, to: f64) -> Result<Value, CalculatorError> {
+    if to - from >= MAX_RANGE {
+        return runtime!(format!("range of more than {} elements", MAX_RANGE));
+    }
+    let length = if to >= from {
+        (to - from).floor() as usize + 1
+    } else {
+        0
+    };
+    Ok(Value::List((0..length).map(|i| from + i as f64).collect()))
+}
+
+// Rows count from 1 too, a list of indices picks several.
+fn row(matrix: &Matrix, index: Value) -> Result<Value, CalculatorError> {
+    let row = |i: f64| {
+        if i.fract() == 0.0 && i >= 1.0 && i <= matrix.rows() as f64 {
+            Ok(matrix.row(i as usize - 1).to_vec())
+        } else {
+            runtime!(format!("no row {} in a matrix of {}", i, matrix.rows()))
+        }
+    };
+    match index {
+        Value::Number(i) => Ok(Value::List(row(i)?)),
+        Value::List(indices) => Ok(Value::Matrix(Matrix::from_rows(
+            indices.into_iter().map(row).collect::<Result<_, _>>()?,
+        )?)),
+        Value::Matrix(_) => runtime!(format!("an index must be a number or a list, not a matrix")),
+    }
+}
+
+// Elements count from 1.
+fn element(list: &[f64], i: f64) -> Result<f64, CalculatorError> {
+    if i.fract() == 0.0 && i >= 1.0 && i <= list.len() as f64 {
+        Ok(list[i as usize - 1])
+    } else {
+        runtime!(format!("no element {} in a list of {}", i, list.len()))
+    }
+}
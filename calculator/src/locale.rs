@@ -0,0 +1,61 @@
+use super::*;
+
+pub const LOCALES: [Locale; 4] = [Locale::EnUs, Locale::DeDe, Locale::FrFr, Locale::DeCh];
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Locale {
+    #[default]
+    EnUs,
+    DeDe,
+    FrFr,
+    DeCh,
+}
+
+impl Locale {
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Locale::EnUs => "en-US",
+            Locale::DeDe => "de-DE",
+            Locale::FrFr => "fr-FR",
+            Locale::DeCh => "de-CH",
+        }
+    }
+
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        LOCALES.iter().find(|locale| locale.tag() == tag).cloned()
+    }
+
+    pub fn decimal_mark(&self) -> char {
+        match self {
+            Locale::EnUs | Locale::DeCh => '.',
+            Locale::DeDe | Locale::FrFr => ',',
+        }
+    }
+
+    pub fn argument_separator(&self) -> char {
+        match self {
+            Locale::EnUs | Locale::DeCh => ',',
+            Locale::DeDe | Locale::FrFr => ';',
+        }
+    }
+
+    /// Separators accepted between digit groups of a literal. `en-US` has
+    /// none because `,` already separates arguments. `fr-FR` also takes a
+    /// plain space, which is what people type for its narrow one.
+    pub fn grouping_separators(&self) -> &'static [char] {
+        match self {
+            Locale::EnUs => &[],
+            Locale::DeDe => &['.'],
+            Locale::FrFr => &['\u{202f}', '\u{a0}', ' '],
+            Locale::DeCh => &['\'', '’'],
+        }
+    }
+
+    pub fn formatter(&self, notation: Notation) -> Formatter {
+        Formatter {
+            thousands_separator: self.grouping_separators().first().cloned(),
+            decimal_mark: self.decimal_mark(),
+            ..Formatter::new(notation)
+        }
+    }
+}
@@ -0,0 +1,431 @@
+use super::*;
+
+// Sweeps of the QR algorithm `eig` makes before it gives up.
+const MAX_SWEEPS: usize = 1000;
+
+// How small next to the largest element a pivot must be for `rank` to count
+// it as zero, well above rounding so that nearly dependent rows count as one.
+const RANK_TOLERANCE: f64 = 1e-10;
+
+/// A matrix of `rows` × `columns` numbers, kept row after row. It has at
+/// least one of each.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    rows: usize,
+    columns: usize,
+    elements: Vec<f64>,
+}
+
+impl Matrix {
+    /// The matrix with `rows[i]` as its row `i`, all of the same length.
+    pub fn from_rows(rows: Vec<Vec<f64>>) -> Result<Self, CalculatorError> {
+        let columns = rows.first().map_or(0, Vec::len);
+        if columns == 0 {
+            return runtime!(format!("a matrix needs rows and columns"));
+        }
+        if let Some(row) = rows.iter().find(|row| row.len() != columns) {
+            return runtime!(format!("rows of {} and {} elements", columns, row.len()));
+        }
+        Ok(Matrix {
+            rows: rows.len(),
+            columns,
+            elements: rows.concat(),
+        })
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let mut identity = Matrix::zero(n, n);
+        for i in 0..n {
+            identity[(i, i)] = 1.0;
+        }
+        identity
+    }
+
+    fn zero(rows: usize, columns: usize) -> Self {
+        Matrix {
+            rows,
+            columns,
+            elements: vec![0.0; rows * columns],
+        }
+    }
+
+    // One column, for a list on the right of a product or of `solve`.
+    pub(crate) fn column(elements: Vec<f64>) -> Self {
+        Matrix {
+            rows: elements.len(),
+            columns: 1,
+            elements,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Row `i`, counting from 0.
+    pub fn row(&self, i: usize) -> &[f64] {
+        &self.elements[i * self.columns..(i + 1) * self.columns]
+    }
+
+    /// All the elements, row after row.
+    pub fn elements(&self) -> &[f64] {
+        &self.elements
+    }
+
+    pub(crate) fn into_elements(self) -> Vec<f64> {
+        self.elements
+    }
+
+    fn is_square(&self) -> bool {
+        self.rows == self.columns
+    }
+
+    // `2×3`, for the errors.
+    fn size(&self) -> String {
+        format!("{}×{}", self.rows, self.columns)
+    }
+
+    fn square(&self, what: &str) -> Result<(), CalculatorError> {
+        if self.is_square() {
+            Ok(())
+        } else {
+            runtime!(format!(
+                "{} of a {} matrix, it must be square",
+                what,
+                self.size()
+            ))
+        }
+    }
+
+    pub(crate) fn try_map(
+        self,
+        f: impl Fn(f64) -> Result<f64, CalculatorError>,
+    ) -> Result<Self, CalculatorError> {
+        Ok(Matrix {
+            elements: self.elements.into_iter().map(f).collect::<Result<_, _>>()?,
+            ..self
+        })
+    }
+
+    // `f` of the elements at the same place in two matrices of one size.
+    pub(crate) fn try_zip(
+        self,
+        other: Matrix,
+        f: impl Fn(f64, f64) -> Result<f64, CalculatorError>,
+    ) -> Result<Self, CalculatorError> {
+        if (self.rows, self.columns) != (other.rows, other.columns) {
+            return runtime!(format!("{} and {} matrices", self.size(), other.size()));
+        }
+        let elements = self
+            .elements
+            .into_iter()
+            .zip(other.elements)
+            .map(|(a, b)| f(a, b));
+        Ok(Matrix {
+            elements: elements.collect::<Result<_, _>>()?,
+            ..self
+        })
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut transpose = Matrix::zero(self.columns, self.rows);
+        for i in 0..self.rows {
+            for j in 0..self.columns {
+                transpose[(j, i)] = self[(i, j)];
+            }
+        }
+        transpose
+    }
+
+    /// The matrix product, `self` needs as many columns as `other` has rows.
+    pub fn mul(&self, other: &Matrix) -> Result<Self, CalculatorError> {
+        if self.columns != other.rows {
+            return runtime!(format!(
+                "cannot multiply a {} matrix by a {} one",
+                self.size(),
+                other.size()
+            ));
+        }
+        let mut product = Matrix::zero(self.rows, other.columns);
+        for i in 0..self.rows {
+            for k in 0..self.columns {
+                let a = self[(i, k)];
+                for j in 0..other.columns {
+                    product[(i, j)] += a * other[(k, j)];
+                }
+            }
+        }
+        Ok(product)
+    }
+
+    // `self` times a column of `vector`.
+    pub(crate) fn mul_vector(&self, vector: Vec<f64>) -> Result<Vec<f64>, CalculatorError> {
+        Ok(self.mul(&Matrix::column(vector))?.elements)
+    }
+
+    /// A square matrix multiplied by itself `n` times, the inverse for
+    /// negative `n`.
+    pub fn pow(&self, n: f64) -> Result<Self, CalculatorError> {
+        self.square("power")?;
+        if n.fract() != 0.0 {
+            return runtime!(format!("a matrix to the power {}, it must be whole", n));
+        }
+        let mut base = if n < 0.0 { self.inv()? } else { self.clone() };
+        let mut power = Matrix::identity(self.rows);
+        let mut n = n.abs();
+        while n > 0.0 {
+            if n % 2.0 == 1.0 {
+                power = power.mul(&base)?;
+            }
+            base = base.mul(&base)?;
+            n = (n / 2.0).floor();
+        }
+        Ok(power)
+    }
+
+    pub fn det(&self) -> Result<f64, CalculatorError> {
+        self.square("determinant")?;
+        let mut a = self.clone();
+        let mut det = 1.0;
+        for j in 0..a.columns {
+            let pivot = a.pivot(j, j);
+            if a[(pivot, j)] == 0.0 {
+                return Ok(0.0);
+            }
+            if pivot != j {
+                a.swap_rows(pivot, j);
+                det = -det;
+            }
+            det *= a[(j, j)];
+            a.eliminate_below(j, j);
+        }
+        Ok(det)
+    }
+
+    pub fn inv(&self) -> Result<Self, CalculatorError> {
+        self.square("inverse")?;
+        self.solve(&Matrix::identity(self.rows))
+    }
+
+    /// The number of independent rows, with pivots below 10⁻¹⁰ of the largest
+    /// element counted as zero.
+    pub fn rank(&self) -> usize {
+        let mut a = self.clone();
+        let tolerance = a.tolerance(RANK_TOLERANCE);
+        let mut rank = 0;
+        for j in 0..a.columns {
+            if rank == a.rows {
+                break;
+            }
+            let pivot = a.pivot(rank, j);
+            if a[(pivot, j)].abs() <= tolerance {
+                continue;
+            }
+            a.swap_rows(pivot, rank);
+            a.eliminate_below(rank, j);
+            rank += 1;
+        }
+        rank
+    }
+
+    /// `x` with `self x = b`, for a square matrix that is not singular.
+    pub fn solve(&self, b: &Matrix) -> Result<Self, CalculatorError> {
+        self.square("solving with")?;
+        if b.rows != self.rows {
+            return runtime!(format!(
+                "cannot solve a {} matrix for a {} one",
+                self.size(),
+                b.size()
+            ));
+        }
+        let (mut a, mut x) = (self.clone(), b.clone());
+        let tolerance = a.tolerance(f64::EPSILON);
+        let n = a.rows;
+        for j in 0..n {
+            let pivot = a.pivot(j, j);
+            if a[(pivot, j)].abs() <= tolerance {
+                return runtime!(format!("singular matrix"));
+            }
+            a.swap_rows(pivot, j);
+            x.swap_rows(pivot, j);
+            for i in j + 1..n {
+                let factor = a[(i, j)] / a[(j, j)];
+                a.add_row(i, j, -factor);
+                x.add_row(i, j, -factor);
+            }
+        }
+        for j in (0..n).rev() {
+            for k in 0..x.columns {
+                let sum: f64 = (j + 1..n).map(|i| a[(j, i)] * x[(i, k)]).sum();
+                x[(j, k)] = (x[(j, k)] - sum) / a[(j, j)];
+            }
+        }
+        Ok(x)
+    }
+
+    /// The eigenvalues of a square matrix, from the smallest, found with the
+    /// shifted QR algorithm. Complex ones are an error.
+    pub fn eig(&self) -> Result<Vec<f64>, CalculatorError> {
+        self.square("eigenvalues")?;
+        if let Some(x) = self.elements.iter().find(|x| !x.is_finite()) {
+            return runtime!(format!("eigenvalues of a matrix with {}", x));
+        }
+        // Scaled down to elements of at most 1, so that the sums in the steps
+        // cannot overflow, and the eigenvalues back up at the end.
+        let scale = self.largest();
+        if scale == 0.0 {
+            return Ok(vec![0.0; self.rows]);
+        }
+        let mut a = self.clone();
+        for x in &mut a.elements {
+            *x /= scale;
+        }
+        let mut values = Vec::with_capacity(a.rows);
+        let mut n = a.rows;
+        let mut sweeps = 0;
+        while n > 0 {
+            let small = |a: &Matrix, i: usize| {
+                a[(i, i - 1)].abs() <= f64::EPSILON * (a[(i, i)].abs() + a[(i - 1, i - 1)].abs())
+            };
+            if n == 1 || small(&a, n - 1) {
+                values.push(a[(n - 1, n - 1)]);
+                n -= 1;
+                continue;
+            }
+            if n == 2 || small(&a, n - 2) {
+                let (x, y) = a.eig2(n - 2)?;
+                values.push(x);
+                values.push(y);
+                n -= 2;
+                continue;
+            }
+            sweeps += 1;
+            if sweeps > MAX_SWEEPS {
+                return runtime!(format!("eigenvalues did not converge"));
+            }
+            a.qr_step(n);
+        }
+        for x in &mut values {
+            *x *= scale;
+        }
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(values)
+    }
+
+    // The eigenvalues of the 2×2 block starting at row and column `i`.
+    fn eig2(&self, i: usize) -> Result<(f64, f64), CalculatorError> {
+        let (a, b, c, d) = (
+            self[(i, i)],
+            self[(i, i + 1)],
+            self[(i + 1, i)],
+            self[(i + 1, i + 1)],
+        );
+        let mean = (a + d) / 2.0;
+        let discriminant = ((a - d) / 2.0).powi(2) + b * c;
+        if discriminant < 0.0 {
+            return runtime!(format!("complex eigenvalues"));
+        }
+        let root = discriminant.sqrt();
+        Ok((mean - root, mean + root))
+    }
+
+    // One QR step on the leading `n` rows and columns, shifted by the corner
+    // element: A - μI = QR, then A = RQ + μI.
+    fn qr_step(&mut self, n: usize) {
+        let shift = self[(n - 1, n - 1)];
+        for i in 0..n {
+            self[(i, i)] -= shift;
+        }
+        let mut rotations = Vec::with_capacity(n * n / 2);
+        for j in 0..n {
+            for i in j + 1..n {
+                let (a, b) = (self[(j, j)], self[(i, j)]);
+                if b == 0.0 {
+                    continue;
+                }
+                let r = a.hypot(b);
+                let (c, s) = (a / r, b / r);
+                for k in 0..n {
+                    let (x, y) = (self[(j, k)], self[(i, k)]);
+                    self[(j, k)] = c * x + s * y;
+                    self[(i, k)] = c * y - s * x;
+                }
+                rotations.push((j, i, c, s));
+            }
+        }
+        for (j, i, c, s) in rotations {
+            for k in 0..n {
+                let (x, y) = (self[(k, j)], self[(k, i)]);
+                self[(k, j)] = c * x + s * y;
+                self[(k, i)] = c * y - s * x;
+            }
+        }
+        for i in 0..n {
+            self[(i, i)] += shift;
+        }
+    }
+
+    // The row from `start` down with the largest element in `column`.
+    fn pivot(&self, start: usize, column: usize) -> usize {
+        (start..self.rows)
+            .max_by(|&a, &b| {
+                let (a, b) = (self[(a, column)].abs(), self[(b, column)].abs());
+                a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .unwrap_or(start)
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        for j in 0..self.columns {
+            self.elements
+                .swap(a * self.columns + j, b * self.columns + j);
+        }
+    }
+
+    // Adds `factor` times row `source` to row `target`.
+    fn add_row(&mut self, target: usize, source: usize, factor: f64) {
+        for j in 0..self.columns {
+            let x = self[(source, j)];
+            self[(target, j)] += factor * x;
+        }
+    }
+
+    // Clears `column` below the pivot in `row`.
+    fn eliminate_below(&mut self, row: usize, column: usize) {
+        for i in row + 1..self.rows {
+            let factor = self[(i, column)] / self[(row, column)];
+            self.add_row(i, row, -factor);
+        }
+    }
+
+    // The largest element by absolute value.
+    fn largest(&self) -> f64 {
+        self.elements
+            .iter()
+            .fold(0.0, |largest: f64, x| largest.max(x.abs()))
+    }
+
+    // What counts as zero after elimination, `relative` to the largest
+    // element.
+    fn tolerance(&self, relative: f64) -> f64 {
+        self.largest() * self.rows.max(self.columns) as f64 * relative
+    }
+}
+
+impl std::ops::Index<(usize, usize)> for Matrix {
+    type Output = f64;
+
+    fn index(&self, (i, j): (usize, usize)) -> &f64 {
+        &self.elements[i * self.columns + j]
+    }
+}
+
+impl std::ops::IndexMut<(usize, usize)> for Matrix {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut f64 {
+        &mut self.elements[i * self.columns + j]
+    }
+}
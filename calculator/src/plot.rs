@@ -0,0 +1,209 @@
+use super::*;
+
+/// The variable functions are plotted over.
+pub const PLOT_VARIABLE: &str = "x";
+
+const SAMPLES: usize = 256;
+const MAX_DEPTH: u32 = 6;
+// Both relative to the spread of the sampled values.
+const TOLERANCE: f64 = 1e-3;
+const JUMP: f64 = 0.05;
+
+/// A connected run of points, graphs break at gaps and discontinuities.
+pub type Segment = Vec<(f64, f64)>;
+
+/// `plot(f, g, ..., from, to)` with any number of functions of `x`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plot {
+    pub functions: Vec<String>,
+    pub from: f64,
+    pub to: f64,
+}
+
+impl Plot {
+    /// Reads a `plot(...)` call, `None` means `expression` is not one.
+    pub fn parse(
+        expression: &str,
+        settings: &Settings,
+        variables: &HashMap<String, f64>,
+    ) -> Option<Result<Plot, CalculatorError>> {
+        let open = Self::open_paren(expression)?;
+        Some(Self::parse_arguments(
+            expression,
+            open + 1,
+            settings,
+            variables,
+        ))
+    }
+
+    /// Whether `expression` is a `plot(...)` call, well formed or not.
+    pub fn is_plot(expression: &str) -> bool {
+        Self::open_paren(expression).is_some()
+    }
+
+    fn open_paren(expression: &str) -> Option<usize> {
+        let rest = expression.trim_start().strip_prefix("plot")?.trim_start();
+        if rest.starts_with('(') {
+            Some(expression.len() - rest.len())
+        } else {
+            None
+        }
+    }
+
+    fn parse_arguments(
+        expression: &str,
+        start: usize,
+        settings: &Settings,
+        variables: &HashMap<String, f64>,
+    ) -> Result<Plot, CalculatorError> {
+        let separator = settings.locale.argument_separator();
+        let mut arguments = vec![];
+        let mut depth = 0;
+        let mut begin = start;
+        let mut end = None;
+        for (i, c) in expression[start..].char_indices() {
+            let i = start + i;
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 0 => {
+                    arguments.push(begin..i);
+                    end = Some(i);
+                    break;
+                }
+                ')' => depth -= 1,
+                c if c == separator && depth == 0 => {
+                    arguments.push(begin..i);
+                    begin = i + c.len_utf8();
+                }
+                _ => {}
+            }
+        }
+        let end = match end {
+            Some(end) => end,
+            None => return syntax!("plot missing RParen".to_string(), expression.len()),
+        };
+        if !expression[end + 1..].trim().is_empty() {
+            return syntax!("plot trailing input".to_string(), end + 1);
+        }
+        if arguments.len() < 3 {
+            return syntax!(format!("plot arity {}", arguments.len()), end);
+        }
+        let bound = |range: std::ops::Range<usize>| {
+            eval_in(&expression[range.clone()], settings, variables)
+                .map_err(|error| error.offset(range.start))
+        };
+        let to = bound(arguments.pop().unwrap())?;
+        let from = bound(arguments.pop().unwrap())?;
+        let mut functions = vec![];
+        for range in arguments {
+            // Catches syntax errors now rather than as an empty graph.
+            Graph::new(&expression[range.clone()], settings, variables)
+                .map_err(|error| error.offset(range.start))?;
+            functions.push(expression[range].trim().to_string());
+        }
+        Ok(Plot {
+            functions,
+            from,
+            to,
+        })
+    }
+}
+
+/// A function of `x`, compiled once and evaluated at many points.
+pub struct Graph {
+    code: CompiledExpr,
+}
+
+impl Graph {
+    pub fn new(
+        function: &str,
+        settings: &Settings,
+        variables: &HashMap<String, f64>,
+    ) -> Result<Self, CalculatorError> {
+        let mut code = CompiledExpr::new(function, &[PLOT_VARIABLE], settings, variables)?;
+        // Unknown names fail the same way for every `x`.
+        if let Err(error @ CalculatorError::Runtime(_)) = code.eval(&[0.0]) {
+            return Err(error);
+        }
+        Ok(Graph { code })
+    }
+
+    /// The value at `x`, NaN where the function is undefined.
+    pub fn at(&mut self, x: f64) -> f64 {
+        self.code.eval(&[x]).unwrap_or(f64::NAN)
+    }
+
+    /// Samples `from..=to`, adding points where the graph bends or leaves
+    /// its domain. Non-finite values and jumps that survive the refinement
+    /// split the graph into separate segments.
+    pub fn sample(&mut self, from: f64, to: f64) -> Vec<Segment> {
+        let step = (to - from) / SAMPLES as f64;
+        let coarse: Vec<(f64, f64)> = (0..=SAMPLES)
+            .map(|i| from + step * i as f64)
+            .map(|x| (x, self.at(x)))
+            .collect();
+        let scale = spread(coarse.iter().map(|&(_, y)| y));
+        let mut points = vec![coarse[0]];
+        for pair in coarse.windows(2) {
+            self.refine(pair[0], pair[1], 0, scale, &mut points);
+        }
+        points
+            .split(|&(_, y)| !y.is_finite())
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| segment.to_vec())
+            .collect()
+    }
+
+    // Pushes the points after `a` up to and including `b`.
+    fn refine(
+        &mut self,
+        a: (f64, f64),
+        b: (f64, f64),
+        depth: u32,
+        scale: f64,
+        points: &mut Vec<(f64, f64)>,
+    ) {
+        let x = (a.0 + b.0) / 2.0;
+        let middle = (x, self.at(x));
+        if depth < MAX_DEPTH {
+            let gap = a.1.is_finite() != b.1.is_finite() || a.1.is_finite() != middle.1.is_finite();
+            let bend = (middle.1 - (a.1 + b.1) / 2.0).abs() > TOLERANCE * scale;
+            if gap || bend {
+                self.refine(a, middle, depth + 1, scale, points);
+                self.refine(middle, b, depth + 1, scale, points);
+                return;
+            }
+        } else {
+            // A steep but continuous graph splits its rise between both
+            // halves, a jump stays in one of them however small they get.
+            let rise = (b.1 - a.1).abs();
+            let half = (middle.1 - a.1).abs().max((b.1 - middle.1).abs());
+            if rise > JUMP * scale && half > 0.9 * rise {
+                points.push((x, f64::NAN));
+            }
+        }
+        points.push(b);
+    }
+}
+
+/// Range of the finite `values` between the 5th and 95th percentile, so
+/// that poles do not flatten everything else. Never zero.
+pub fn spread(values: impl Iterator<Item = f64>) -> f64 {
+    let (low, high) = percentiles(values);
+    if high > low {
+        high - low
+    } else {
+        1.0
+    }
+}
+
+/// The 5th and 95th percentile of the finite `values`.
+pub fn percentiles(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let mut values: Vec<f64> = values.filter(|y| y.is_finite()).collect();
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let last = values.len() - 1;
+    (values[last / 20], values[last - last / 20])
+}
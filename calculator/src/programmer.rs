@@ -0,0 +1,103 @@
+/// Widths a word can have. Values are `f64`s, which hold every integer of
+/// a 32-bit word but not of a 64-bit one.
+pub const WORD_BITS: [u32; 3] = [8, 16, 32];
+
+/// 2⁵³ - 1, up to which every integer is exact in an `f64`. Radix literals
+/// may not go past it.
+pub const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct WordSize {
+    bits: u32,
+    signed: bool,
+}
+
+impl WordSize {
+    /// A word of `bits`, `None` unless it is one of [`WORD_BITS`].
+    pub fn new(bits: u32, signed: bool) -> Option<Self> {
+        if WORD_BITS.contains(&bits) {
+            Some(WordSize { bits, signed })
+        } else {
+            None
+        }
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    pub fn signed(&self) -> bool {
+        self.signed
+    }
+
+    /// Truncates `x` to an integer and wraps it around the word, the way
+    /// fixed width registers overflow.
+    pub fn wrap(&self, x: f64) -> f64 {
+        let n = self.wrap_int(x as i128);
+        n as f64
+    }
+
+    fn wrap_int(&self, n: i128) -> i128 {
+        let mask = self.mask();
+        let n = n & mask;
+        if self.signed && n >> (self.bits - 1) & 1 == 1 {
+            n - mask - 1
+        } else {
+            n
+        }
+    }
+
+    fn mask(&self) -> i128 {
+        (1i128 << self.bits) - 1
+    }
+
+    /// Formats `x` in `radix`. Negative numbers are shown in two's
+    /// complement except in decimal. `None` for a radix outside 2 to 36.
+    pub fn format(&self, x: f64, radix: u32) -> Option<String> {
+        let n = self.wrap_int(x as i128);
+        if radix == 10 {
+            Some(n.to_string())
+        } else {
+            Some(format!(
+                "{}{}",
+                radix_prefix(radix),
+                to_radix((n & self.mask()) as u128, radix)?
+            ))
+        }
+    }
+}
+
+impl Default for WordSize {
+    fn default() -> Self {
+        WordSize {
+            bits: 32,
+            signed: true,
+        }
+    }
+}
+
+/// `0x` and the like, the prefixes radix literals are read with.
+pub fn radix_prefix(radix: u32) -> &'static str {
+    match radix {
+        2 => "0b",
+        8 => "0o",
+        16 => "0x",
+        _ => "",
+    }
+}
+
+/// The digits of `n` in `radix`, `None` for a radix outside 2 to 36.
+pub fn to_radix(mut n: u128, radix: u32) -> Option<String> {
+    if !(2..=36).contains(&radix) {
+        return None;
+    }
+    let mut digits = vec![];
+    loop {
+        digits.push(std::char::from_digit((n % radix as u128) as u32, radix).unwrap());
+        n /= radix as u128;
+        if n == 0 {
+            break;
+        }
+    }
+    Some(digits.iter().rev().collect::<String>().to_uppercase())
+}
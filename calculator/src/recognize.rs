@@ -0,0 +1,108 @@
+use super::*;
+
+const MAX_DENOMINATOR: i64 = 1000;
+const TOLERANCE: f64 = 1e-10;
+
+const MULTIPLES: [(&str, f64); 8] = [
+    ("", 1.0),
+    ("π", PI),
+    ("π²", PI * PI),
+    ("e", E),
+    ("√2", SQRT_2),
+    ("√3", 1.732_050_807_568_877_2),
+    ("√5", 2.236_067_977_499_79),
+    ("ln2", LN_2),
+];
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Candidate {
+    pub expression: String,
+    pub value: f64,
+    /// Relative error between the closed form and the recognized number.
+    pub error: f64,
+}
+
+/// Looks for closed forms `p/q·c` close to `x` where `c` is one of a few
+/// well known constants, simplest first. Integers are not reported.
+pub fn recognize(x: f64) -> Vec<Candidate> {
+    if !x.is_finite() || x == 0.0 || x.abs() > 1e9 || x == x.trunc() {
+        return vec![];
+    }
+    let mut candidates = vec![];
+    for &(name, constant) in MULTIPLES.iter() {
+        if let Some((p, q)) = rational(x / constant, MAX_DENOMINATOR) {
+            if p == 0 {
+                continue;
+            }
+            let value = p as f64 / q as f64 * constant;
+            let error = ((value - x) / x).abs();
+            if error < TOLERANCE {
+                candidates.push((
+                    q,
+                    Candidate {
+                        expression: closed_form(p, q, name),
+                        value,
+                        error,
+                    },
+                ));
+            }
+        }
+    }
+    candidates.sort_by_key(|(q, _)| *q);
+    candidates
+        .into_iter()
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+fn closed_form(p: i64, q: i64, name: &str) -> String {
+    let sign = if p < 0 { "-" } else { "" };
+    let p = p.abs();
+    let numerator = match (p, name) {
+        (_, "") => p.to_string(),
+        (1, _) => name.to_string(),
+        _ => format!("{}{}", p, name),
+    };
+    if q == 1 {
+        format!("{}{}", sign, numerator)
+    } else {
+        format!("{}{}/{}", sign, numerator, q)
+    }
+}
+
+/// Best rational approximation of `x` by continued fractions whose
+/// denominator does not exceed `max_denominator`.
+fn rational(x: f64, max_denominator: i64) -> Option<(i64, i64)> {
+    if !x.is_finite() || x.abs() > 1e12 {
+        return None;
+    }
+    let (mut h0, mut h1) = (0i64, 1i64);
+    let (mut k0, mut k1) = (1i64, 0i64);
+    let mut y = x;
+    loop {
+        let a = y.floor();
+        if a.abs() > 1e12 {
+            break;
+        }
+        let a = a as i64;
+        let h2 = a * h1 + h0;
+        let k2 = a * k1 + k0;
+        if k2 > max_denominator {
+            break;
+        }
+        h0 = h1;
+        h1 = h2;
+        k0 = k1;
+        k1 = k2;
+        let fraction = y - a as f64;
+        if fraction.abs() < 1e-12 {
+            break;
+        }
+        y = 1.0 / fraction;
+    }
+    if k1 == 0 {
+        None
+    } else {
+        Some((h1, k1))
+    }
+}
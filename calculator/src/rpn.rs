@@ -0,0 +1,278 @@
+use super::*;
+use anyhow::Result;
+use errors::CalculatorError;
+
+
+type PrevTok = Option<Tok>;
+
+trait TokenHelper {
+    fn is_operand(&self) -> bool;
+    fn is_call(&self) -> bool;
+}
+
+impl TokenHelper for PrevTok {
+    fn is_operand(&self) -> bool {
+        if let Some(tok) = self {
+            tok.is_operand()
+        } else {
+            false
+        }
+    }
+    fn is_call(&self) -> bool {
+        if let Some(Tok::Call(_, _)) = self {
+            true
+        } else {
+            false
+        }
+    }
+}
+
+pub struct RPN {
+    stack: Vec<Tok>,
+    pub output: Vec<Tok>,
+    prev_tok: PrevTok,
+    jumps: Vec<usize>,
+}
+
+impl RPN {
+    pub fn new() -> Self {
+        let stack = vec![];
+        let output = vec![];
+        let prev_tok = None;
+        let jumps = vec![];
+        RPN {
+            stack,
+            output,
+            prev_tok,
+            jumps,
+        }
+    }
+
+    pub fn push_all(&mut self, tokens: Vec<Token>) -> Result<(), CalculatorError> {
+        for token in tokens {
+            self.push(token)?;
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, token: Token) -> Result<(), CalculatorError> {
+        let tok = token.tok;
+        let pos = token.pos;
+        let priority = tok.priority();
+        match tok {
+            Tok::Number(_) | Tok::Const(_) => {
+                if self.prev_tok.is_operand() {
+                    self.push(Token::new(TOK_MUL, pos))?;
+                }
+                self.output.push(tok.clone());
+            }
+            Tok::RParen => {
+                if self.prev_tok.is_call() || !self.prev_tok.is_operand() {
+                    return syntax!(format!("syntax RParen 1"), pos);
+                }
+                self.pop_higher(priority, pos)?;
+                let t = self.stack.last().cloned();
+                if t.is_call() {
+                    self.pop(pos)?;
+                } else {
+                    if t.is_some() {
+                        if t == Some(Tok::LParen) {
+                            self.stack.pop().unwrap();
+                        } else {
+                            return syntax!(format!("syntax RParen 2"), pos);
+                        }
+                    }
+                }
+            }
+            Tok::LBracket => {
+                // After a value it is an index, otherwise a list.
+                if self.prev_tok.is_operand() {
+                    while self.stack.last() == Some(&Tok::Factorial) {
+                        self.pop(pos)?;
+                    }
+                    self.stack.push(Tok::Index);
+                } else {
+                    self.stack.push(Tok::List(1));
+                }
+            }
+            Tok::RBracket => {
+                if self.prev_tok == Some(Tok::LBracket) && self.stack.last() == Some(&Tok::List(1))
+                {
+                    self.stack.pop();
+                    self.output.push(Tok::List(0));
+                } else {
+                    if !self.prev_tok.is_operand() {
+                        return syntax!(format!("syntax RBracket 1"), pos);
+                    }
+                    self.pop_higher(priority, pos)?;
+                    match self.stack.pop() {
+                        Some(tok @ Tok::List(_)) | Some(tok @ Tok::Index) => self.output.push(tok),
+                        _ => return syntax!(format!("syntax RBracket 2"), pos),
+                    }
+                }
+            }
+            Tok::Comma => {
+                if !self.prev_tok.is_operand() {
+                    return syntax!(format!("syntax Comma 1"), pos);
+                }
+                self.pop_higher(priority, pos)?;
+                match self.stack.last().cloned() {
+                    Some(Tok::List(length)) => {
+                        self.stack.pop();
+                        self.stack.push(Tok::List(length + 1));
+                    }
+                    Some(Tok::Call(name, arity)) => {
+                        if name == "if" {
+                            match arity {
+                                1 => self.jump_unless(),
+                                2 => self.jump_else(),
+                                _ => return syntax!(format!("syntax Comma 3"), pos),
+                            }
+                        }
+                        self.stack.pop();
+                        self.stack.push(Tok::Call(name, arity + 1));
+                    }
+                    _ => return syntax!(format!("syntax Comma 2"), pos),
+                }
+            }
+            Tok::Question => {
+                if !self.prev_tok.is_operand() {
+                    return syntax!(format!("syntax Question 1"), pos);
+                }
+                self.pop_higher(priority + 1, pos)?;
+                self.jump_unless();
+                self.stack.push(tok.clone());
+            }
+            Tok::Colon => {
+                if !self.prev_tok.is_operand() {
+                    return syntax!(format!("syntax Colon 1"), pos);
+                }
+                self.pop_higher(priority + 1, pos)?;
+                while self.stack.last() == Some(&Tok::Colon) {
+                    self.pop(pos)?;
+                }
+                if self.stack.last() != Some(&Tok::Question) {
+                    return syntax!(format!("syntax Colon 2"), pos);
+                }
+                self.stack.pop();
+                self.jump_else();
+                self.stack.push(tok.clone());
+            }
+            Tok::Jump(_) | Tok::JumpUnless(_) | Tok::List(_) | Tok::Index => {
+                return syntax!(format!("syntax Jump 1"), pos);
+            }
+            Tok::End => {
+                while !self.stack.is_empty() {
+                    self.push(Token::new(Tok::RParen, pos))?;
+                }
+            }
+            Tok::Operator(_) | Tok::LParen | Tok::Call(_, _) | Tok::Factorial => {
+                if tok.is_prefix() {
+                    if self.prev_tok.is_operand() {
+                        self.push(Token::new(TOK_MUL, pos))?;
+                    }
+                    self.stack.push(tok.clone());
+                } else {
+                    if !self.prev_tok.is_operand() {
+                        match tok {
+                            TOK_SUB => {
+                                self.stack.push(TOK_UMIN);
+                            }
+                            TOK_ADD => {
+                                return Ok(());
+                            }
+                            _ => {
+                                return syntax!(format!("syntax Operator 1"), pos);
+                            }
+                        }
+                    } else {
+                        self.pop_higher(
+                            priority + if tok.is_right_associtive() { 1 } else { 0 },
+                            pos,
+                        )?;
+                        if tok == TOK_AND || tok == TOK_OR {
+                            self.jump_unless();
+                        }
+                        if tok == TOK_OR {
+                            self.output.push(Tok::Number("1".to_string()));
+                            self.jump_else();
+                        }
+                        self.stack.push(tok.clone());
+                    }
+                }
+            }
+        }
+        self.prev_tok = Some(tok);
+        Ok(())
+    }
+
+    fn pop_higher(&mut self, priority: i32, pos: usize) -> Result<(), CalculatorError> {
+        while let Some(tok) = self.stack.last() {
+            if tok.priority() >= priority {
+                self.pop(pos)?;
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn pop(&mut self, pos: usize) -> Result<(), CalculatorError> {
+        match self.stack.pop().unwrap() {
+            Tok::Question => {
+                return syntax!(format!("syntax Question 2"), pos);
+            }
+            Tok::Colon => {
+                self.land();
+            }
+            Tok::Call(name, arity) if name == "if" => {
+                if arity != 3 {
+                    return syntax!(format!("syntax If 1"), pos);
+                }
+                self.land();
+            }
+            TOK_AND => {
+                self.output.push(TOK_AND);
+                self.jump_else();
+                self.output.push(Tok::Number("0".to_string()));
+                self.land();
+            }
+            TOK_OR => {
+                self.output.push(TOK_OR);
+                self.land();
+            }
+            tok => {
+                self.output.push(tok);
+            }
+        }
+        Ok(())
+    }
+
+    // Conditionals are lowered to jumps whose targets are patched by `land`
+    // once the end of the skipped branch is known.
+    fn jump_unless(&mut self) {
+        self.jumps.push(self.output.len());
+        self.output.push(Tok::JumpUnless(0));
+    }
+
+    fn jump(&mut self) {
+        self.jumps.push(self.output.len());
+        self.output.push(Tok::Jump(0));
+    }
+
+    fn jump_else(&mut self) {
+        let pending = self.jumps.pop().unwrap();
+        self.jump();
+        self.jumps.push(pending);
+        self.land();
+    }
+
+    fn land(&mut self) {
+        let target = self.output.len();
+        let index = self.jumps.pop().unwrap();
+        match &mut self.output[index] {
+            Tok::Jump(t) | Tok::JumpUnless(t) => *t = target,
+            _ => unreachable!(),
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use super::*;
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Angle {
+    #[default]
+    Radians,
+    Degrees,
+}
+
+impl Angle {
+    pub fn to_radians(&self, x: f64) -> f64 {
+        match self {
+            Angle::Radians => x,
+            Angle::Degrees => x.to_radians(),
+        }
+    }
+
+    pub fn from_radians(&self, x: f64) -> f64 {
+        match self {
+            Angle::Radians => x,
+            Angle::Degrees => x.to_degrees(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Settings {
+    /// When set, every intermediate result is an integer wrapped to the word.
+    pub word_size: Option<WordSize>,
+    pub locale: Locale,
+    pub angle: Angle,
+}
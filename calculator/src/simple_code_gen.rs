@@ -0,0 +1,220 @@
+use super::*;
+use std::collections::HashMap;
+use anyhow::Result;
+#[cfg(feature = "random")]
+use rand::prelude::*;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Function {
+    Sin,
+    Cos,
+    Log,
+    Log2,
+    Log10,
+    Ln,
+    Lg,
+    Lb,
+    Asin,
+    Acos,
+    Rnd,
+    Max,
+    Min,
+    Sqrt,
+    Cbrt,
+    Sum,
+    Prod,
+    Mean,
+    Len,
+    Det,
+    Inv,
+    Transpose,
+    Rank,
+    Solve,
+    Eig,
+}
+
+impl Function {
+    /// How many arguments a call takes.
+    pub(crate) fn arity(&self) -> usize {
+        match self {
+            Function::Log | Function::Rnd | Function::Max | Function::Min | Function::Solve => 2,
+            _ => 1,
+        }
+    }
+
+    /// Whether it reduces a list to a number rather than going over its
+    /// elements. A number counts as a list of one.
+    pub(crate) fn is_aggregate(&self) -> bool {
+        matches!(
+            self,
+            Function::Sum | Function::Prod | Function::Mean | Function::Len
+        )
+    }
+
+    /// Whether it takes a matrix as a whole. A number counts as a matrix
+    /// of one.
+    pub(crate) fn is_matrix(&self) -> bool {
+        matches!(
+            self,
+            Function::Det
+                | Function::Inv
+                | Function::Transpose
+                | Function::Rank
+                | Function::Solve
+                | Function::Eig
+        )
+    }
+
+    /// The value for `args`, which has `arity()` elements.
+    pub(crate) fn apply(&self, args: &[f64], angle: Angle) -> Result<f64, CalculatorError> {
+        // `y` is the first of two arguments, `x` the last one.
+        let (y, x) = (args[0], args[args.len() - 1]);
+        let z = match self {
+            Function::Sin => angle.to_radians(x).sin(),
+            Function::Cos => angle.to_radians(x).cos(),
+            Function::Log => x.log(y),
+            Function::Ln => x.ln(),
+            Function::Log10 | Function::Lg => x.log(10.0),
+            Function::Log2 | Function::Lb => x.log(2.0),
+            Function::Asin => angle.from_radians(x.asin()),
+            Function::Acos => angle.from_radians(x.acos()),
+            #[cfg(feature = "random")]
+            Function::Rnd => rand::thread_rng().gen_range(y, x),
+            #[cfg(not(feature = "random"))]
+            Function::Rnd => return runtime!("rnd needs the `random` feature".to_string()),
+            Function::Max => x.max(y),
+            Function::Min => x.min(y),
+            Function::Sqrt => x.sqrt(),
+            Function::Cbrt => x.cbrt(),
+            Function::Sum | Function::Prod | Function::Mean => x,
+            Function::Len => 1.0,
+            Function::Det | Function::Transpose | Function::Eig => x,
+            Function::Inv => 1.0 / x,
+            Function::Rank => (x != 0.0) as i32 as f64,
+            Function::Solve => x / y,
+        };
+        Ok(z)
+    }
+}
+
+pub struct SimpleCodeGen {
+    rpn: Vec<Tok>,
+    consts: HashMap<String, f64>,
+    lists: HashMap<String, Value>,
+    functions: HashMap<String, Function>,
+    pub word_size: Option<WordSize>,
+    pub angle: Angle,
+}
+
+impl SimpleCodeGen {
+    pub fn new(rpn: Vec<Tok>) -> Self {
+        let mut consts = HashMap::new();
+        let mut functions = HashMap::new();
+        consts.insert("π".to_string(), PI);
+        consts.insert("pi".to_string(), PI);
+        consts.insert("e".to_string(), E);
+        consts.insert("NaN".to_string(), NAN);
+        consts.insert("Inf".to_string(), INFINITY);
+        consts.insert("Infinity".to_string(), INFINITY);
+        consts.insert("true".to_string(), 1.0);
+        consts.insert("false".to_string(), 0.0);
+        functions.insert("sin".to_string(), Function::Sin);
+        functions.insert("cos".to_string(), Function::Cos);
+        functions.insert("log".to_string(), Function::Log);
+        functions.insert("asin".to_string(), Function::Asin);
+        functions.insert("acos".to_string(), Function::Acos);
+        functions.insert("ln".to_string(), Function::Ln);
+        functions.insert("log2".to_string(), Function::Log2);
+        functions.insert("log10".to_string(), Function::Log10);
+        functions.insert("lb".to_string(), Function::Lb);
+        functions.insert("lg".to_string(), Function::Lg);
+        functions.insert("rnd".to_string(), Function::Rnd);
+        functions.insert("max".to_string(), Function::Max);
+        functions.insert("min".to_string(), Function::Min);
+        functions.insert("sqrt".to_string(), Function::Sqrt);
+        functions.insert("cbrt".to_string(), Function::Cbrt);
+        functions.insert("sum".to_string(), Function::Sum);
+        functions.insert("prod".to_string(), Function::Prod);
+        functions.insert("mean".to_string(), Function::Mean);
+        functions.insert("len".to_string(), Function::Len);
+        functions.insert("det".to_string(), Function::Det);
+        functions.insert("inv".to_string(), Function::Inv);
+        functions.insert("transpose".to_string(), Function::Transpose);
+        functions.insert("rank".to_string(), Function::Rank);
+        functions.insert("solve".to_string(), Function::Solve);
+        functions.insert("eig".to_string(), Function::Eig);
+        let word_size = None;
+        let angle = Angle::Radians;
+        SimpleCodeGen {
+            rpn,
+            consts,
+            lists: HashMap::new(),
+            functions,
+            word_size,
+            angle,
+        }
+    }
+    pub fn insert_const(&mut self, name: &str, value: f64) {
+        self.consts.insert(name.to_string(), value);
+    }
+
+    /// A name for a list, it hides a constant of the same name.
+    pub fn insert_list(&mut self, name: &str, list: Vec<f64>) {
+        self.lists.insert(name.to_string(), Value::List(list));
+    }
+
+    /// A name for a matrix, like [`insert_list`](Self::insert_list).
+    pub fn insert_matrix(&mut self, name: &str, matrix: Matrix) {
+        self.lists.insert(name.to_string(), Value::Matrix(matrix));
+    }
+
+    pub fn has_const(&self, name: &str) -> bool {
+        self.consts.contains_key(name)
+    }
+
+    pub fn has_function(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
+    /// Lowers the expression to bytecode, `variables` become the slots of
+    /// `Program::run` and hide the constants of the same name.
+    pub(crate) fn program(&self, variables: &[&str]) -> Result<Program, CalculatorError> {
+        Program::compile(
+            &self.rpn,
+            variables,
+            &self.consts,
+            &self.functions,
+            self.word_size,
+            self.angle,
+        )
+    }
+
+    pub fn eval(&mut self) -> Result<f64, CalculatorError> {
+        self.eval_value()?.number()
+    }
+
+    pub fn eval_value(&self) -> Result<Value, CalculatorError> {
+        if self.uses_lists() {
+            return eval_list(
+                &self.rpn,
+                &self.consts,
+                &self.lists,
+                &self.functions,
+                self.word_size,
+                self.angle,
+            );
+        }
+        let program = self.program(&[])?;
+        program
+            .run(&mut program.registers(), &[])
+            .map(Value::Number)
+    }
+
+    fn uses_lists(&self) -> bool {
+        self.rpn.iter().any(|tok| match tok {
+            Tok::List(_) | Tok::Index | &TOK_RANGE => true,
+            Tok::Const(name) => self.lists.contains_key(name),
+            _ => false,
+        })
+    }
+}
@@ -0,0 +1,149 @@
+pub const TOK_ADD: Tok = Tok::Operator(Op::Add);
+pub const TOK_SUB: Tok = Tok::Operator(Op::Sub);
+pub const TOK_MUL: Tok = Tok::Operator(Op::Mul);
+pub const TOK_DIV: Tok = Tok::Operator(Op::Div);
+pub const TOK_MOD: Tok = Tok::Operator(Op::Mod);
+pub const TOK_UMIN: Tok = Tok::Operator(Op::Umin);
+pub const TOK_POWER: Tok = Tok::Operator(Op::Power);
+pub const TOK_SQRT: Tok = Tok::Operator(Op::Sqrt);
+pub const TOK_CBRT: Tok = Tok::Operator(Op::Cbrt);
+pub const TOK_LT: Tok = Tok::Operator(Op::Lt);
+pub const TOK_LE: Tok = Tok::Operator(Op::Le);
+pub const TOK_GT: Tok = Tok::Operator(Op::Gt);
+pub const TOK_GE: Tok = Tok::Operator(Op::Ge);
+pub const TOK_EQ: Tok = Tok::Operator(Op::Eq);
+pub const TOK_NE: Tok = Tok::Operator(Op::Ne);
+pub const TOK_AND: Tok = Tok::Operator(Op::And);
+pub const TOK_OR: Tok = Tok::Operator(Op::Or);
+pub const TOK_NOT: Tok = Tok::Operator(Op::Not);
+pub const TOK_BIT_AND: Tok = Tok::Operator(Op::BitAnd);
+pub const TOK_BIT_OR: Tok = Tok::Operator(Op::BitOr);
+pub const TOK_BIT_XOR: Tok = Tok::Operator(Op::BitXor);
+pub const TOK_BIT_NOT: Tok = Tok::Operator(Op::BitNot);
+pub const TOK_SHL: Tok = Tok::Operator(Op::Shl);
+pub const TOK_SHR: Tok = Tok::Operator(Op::Shr);
+pub const TOK_RANGE: Tok = Tok::Operator(Op::Range);
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Tok {
+    Number(String),
+    Const(String),
+    Call(String, usize),
+    Operator(Op),
+    Factorial,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Question,
+    Colon,
+    Jump(usize),
+    JumpUnless(usize),
+    /// A list of that many elements, from the stack in RPN.
+    List(usize),
+    /// The element of a list, in RPN after the list and the index.
+    Index,
+    End,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Op {
+    Add,
+    Sub,
+    Mod,
+    Mul,
+    Div,
+    Umin,
+    Power,
+    Sqrt,
+    Cbrt,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+    Not,
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    Shl,
+    Shr,
+    Range,
+}
+
+impl Tok {
+    pub fn is_right_associtive(&self) -> bool {
+        match self {
+            Tok::Operator(Op::Power) => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_prefix(&self) -> bool {
+        match self {
+            Tok::Call(_, _)
+            | Tok::LParen
+            | &TOK_UMIN
+            | &TOK_SQRT
+            | &TOK_CBRT
+            | &TOK_NOT
+            | &TOK_BIT_NOT => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_operand(&self) -> bool {
+        match self {
+            Tok::Number(_) | Tok::Const(_) | Tok::RParen | Tok::RBracket | Tok::Factorial => true,
+            _ => false,
+        }
+    }
+
+    pub fn priority(&self) -> i32 {
+        match self {
+            Tok::End
+            | Tok::Call(_, _)
+            | Tok::Jump(_)
+            | Tok::JumpUnless(_)
+            | Tok::List(_)
+            | Tok::Index => 0,
+            Tok::LParen | Tok::LBracket => 1,
+            Tok::Comma => 2,
+            Tok::RParen | Tok::RBracket => 3,
+            Tok::Question | Tok::Colon => 4,
+            &TOK_OR => 5,
+            &TOK_AND => 6,
+            &TOK_BIT_OR => 7,
+            &TOK_BIT_XOR => 8,
+            &TOK_BIT_AND => 9,
+            &TOK_EQ | &TOK_NE => 10,
+            &TOK_LT | &TOK_LE | &TOK_GT | &TOK_GE => 11,
+            &TOK_SHL | &TOK_SHR | &TOK_RANGE => 12,
+            &TOK_ADD | &TOK_SUB => 13,
+            &TOK_MUL | &TOK_DIV | &TOK_MOD => 14,
+            &TOK_UMIN | &TOK_NOT | &TOK_BIT_NOT => 15,
+            &TOK_POWER => 16,
+            Tok::Factorial => 17,
+            &TOK_SQRT | &TOK_CBRT => 19,
+            Tok::Number(_) | Tok::Const(_) => 30,
+        }
+    }
+}
+
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Token {
+    pub tok: Tok,
+    pub pos: usize,
+}
+
+impl Token {
+    pub fn new(tok: Tok, pos: usize) -> Self {
+        Token { tok, pos }
+    }
+}
@@ -0,0 +1,169 @@
+use super::*;
+use std::fmt;
+use std::num::ParseFloatError;
+use std::str::FromStr;
+
+/// What an expression evaluates to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    List(Vec<f64>),
+    Matrix(Matrix),
+}
+
+impl Value {
+    /// The number, an error for a list or a matrix.
+    pub fn number(self) -> Result<f64, CalculatorError> {
+        match self {
+            Value::Number(x) => Ok(x),
+            other => runtime!(format!("expected a number, got a {}", other.kind())),
+        }
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    /// `number`, `list` or `matrix`, for the errors.
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "number",
+            Value::List(_) => "list",
+            Value::Matrix(_) => "matrix",
+        }
+    }
+
+    pub(crate) fn try_map(
+        self,
+        f: impl Fn(f64) -> Result<f64, CalculatorError>,
+    ) -> Result<Value, CalculatorError> {
+        Ok(match self {
+            Value::Number(x) => Value::Number(f(x)?),
+            Value::List(list) => Value::List(list.into_iter().map(f).collect::<Result<_, _>>()?),
+            Value::Matrix(matrix) => Value::Matrix(matrix.try_map(f)?),
+        })
+    }
+
+    /// `f` of the elements of two lists of the same length or two matrices
+    /// of the same size, a number going with every element of the other.
+    pub(crate) fn try_zip(
+        self,
+        other: Value,
+        f: impl Fn(f64, f64) -> Result<f64, CalculatorError>,
+    ) -> Result<Value, CalculatorError> {
+        Ok(match (self, other) {
+            (Value::Number(a), Value::Number(b)) => Value::Number(f(a, b)?),
+            (Value::List(a), Value::Number(b)) => {
+                Value::List(a.into_iter().map(|a| f(a, b)).collect::<Result<_, _>>()?)
+            }
+            (Value::Number(a), Value::List(b)) => {
+                Value::List(b.into_iter().map(|b| f(a, b)).collect::<Result<_, _>>()?)
+            }
+            (Value::List(a), Value::List(b)) => {
+                if a.len() != b.len() {
+                    return runtime!(format!("lists of {} and {} elements", a.len(), b.len()));
+                }
+                Value::List(
+                    a.into_iter()
+                        .zip(b)
+                        .map(|(a, b)| f(a, b))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            (Value::Matrix(a), Value::Number(b)) => Value::Matrix(a.try_map(|a| f(a, b))?),
+            (Value::Number(a), Value::Matrix(b)) => Value::Matrix(b.try_map(|b| f(a, b))?),
+            (Value::Matrix(a), Value::Matrix(b)) => Value::Matrix(a.try_zip(b, f)?),
+            _ => return runtime!(format!("a list and a matrix do not go together")),
+        })
+    }
+}
+
+impl Default for Value {
+    fn default() -> Self {
+        Value::Number(0.0)
+    }
+}
+
+impl PartialEq<f64> for Value {
+    fn eq(&self, other: &f64) -> bool {
+        self.as_number() == Some(*other)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(x: f64) -> Self {
+        Value::Number(x)
+    }
+}
+
+impl From<Vec<f64>> for Value {
+    fn from(list: Vec<f64>) -> Self {
+        Value::List(list)
+    }
+}
+
+impl From<Matrix> for Value {
+    fn from(matrix: Matrix) -> Self {
+        Value::Matrix(matrix)
+    }
+}
+
+/// Numbers as `f64` prints them, lists as `[1, 2, 3]` and matrices as
+/// `[[1, 2], [3, 4]]`. It reads back with `parse`, for display use
+/// [`Formatter::format_value`].
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |list: &[f64]| {
+            let elements: Vec<String> = list.iter().map(f64::to_string).collect();
+            format!("[{}]", elements.join(", "))
+        };
+        match self {
+            Value::Number(x) => write!(f, "{}", x),
+            Value::List(elements) => write!(f, "{}", list(elements)),
+            Value::Matrix(matrix) => {
+                let rows: Vec<String> = (0..matrix.rows()).map(|i| list(matrix.row(i))).collect();
+                write!(f, "[{}]", rows.join(", "))
+            }
+        }
+    }
+}
+
+impl FromStr for Value {
+    type Err = CalculatorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match brackets(s) {
+            Some(rows) if rows.trim_start().starts_with('[') => {
+                let rows = rows
+                    .split(']')
+                    .filter_map(|row| brackets(&format!("{}]", row.trim_start_matches(','))));
+                let rows = rows.map(|row| list(&row)).collect::<Result<_, _>>();
+                Ok(Value::Matrix(Matrix::from_rows(
+                    rows.map_err(parse_error)?,
+                )?))
+            }
+            Some(elements) => Ok(Value::List(list(&elements).map_err(parse_error)?)),
+            None => Ok(Value::Number(s.trim().parse().map_err(parse_error)?)),
+        }
+    }
+}
+
+// What is inside `[...]`.
+fn brackets(s: &str) -> Option<String> {
+    let s = s.trim().strip_prefix('[')?.strip_suffix(']')?;
+    Some(s.to_string())
+}
+
+fn list(elements: &str) -> Result<Vec<f64>, ParseFloatError> {
+    if elements.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    elements.split(',').map(|x| x.trim().parse()).collect()
+}
+
+fn parse_error(error: ParseFloatError) -> CalculatorError {
+    CalculatorError::Runtime(error.to_string())
+}
@@ -0,0 +1,13 @@
+use std::env;
+use std::path::PathBuf;
+
+// Keeps `include/calculator.h` in step with the exported functions.
+fn main() {
+    let crate_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+    cbindgen::generate_with_config(&crate_dir, config)
+        .expect("cannot generate the C header")
+        .write_to_file(crate_dir.join("include").join("calculator.h"));
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+}
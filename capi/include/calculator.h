@@ -0,0 +1,120 @@
+#ifndef CALCULATOR_H
+#define CALCULATOR_H
+
+/* Generated by cbindgen from capi/src/lib.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/*
+ Outcome of a call. The numbers are part of the interface and stay as
+ they are.
+ */
+typedef enum CalcStatus {
+  CALC_STATUS_OK = 0,
+  CALC_STATUS_SYNTAX_ERROR = 1,
+  CALC_STATUS_RUNTIME_ERROR = 2,
+  /*
+   A null pointer, a string that is not UTF-8 or a bad variable name.
+   */
+  CALC_STATUS_INVALID_ARGUMENT = 3,
+  /*
+   A bug in the calculator, the session is still usable.
+   */
+  CALC_STATUS_INTERNAL_ERROR = 4,
+} CalcStatus;
+
+/*
+ Variables, earlier results and the last error. Create it with
+ `calc_session_new` and release it with `calc_session_free`.
+ */
+typedef struct CalcSession CalcSession;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ Evaluates `expression` without variables and stores the result in
+ `*value`, which is left alone on errors.
+
+ # Safety
+
+ `expression` must be a NUL terminated string and `value` must point to a
+ writable double, or either may be null.
+ */
+enum CalcStatus calc_eval(const char *expression, double *value);
+
+/*
+ A new session, to be released with `calc_session_free`.
+ */
+struct CalcSession *calc_session_new(void);
+
+/*
+ Releases `session`, null is ignored.
+
+ # Safety
+
+ `session` must come from `calc_session_new` and not be used afterwards.
+ */
+void calc_session_free(struct CalcSession *session);
+
+/*
+ Evaluates `expression` in `session` and stores the result in `*value`.
+ `name = expression` also stores the result as a variable and `ans` is the
+ last result, like on the page. A result that is a list or a matrix has
+ no place in a double, it is a runtime error that leaves the session as it
+ was.
+
+ # Safety
+
+ `session` must be a live session, `expression` a NUL terminated string
+ and `value` must point to a writable double or be null.
+ */
+enum CalcStatus calc_session_eval(struct CalcSession *session,
+                                  const char *expression,
+                                  double *value);
+
+/*
+ Sets the variable `name` in `session`, replacing an earlier value. Names
+ that could not be typed or would hide a built-in like `pi` are an
+ invalid argument.
+
+ # Safety
+
+ `session` must be a live session and `name` a NUL terminated string.
+ */
+enum CalcStatus calc_session_set_variable(struct CalcSession *session,
+                                          const char *name,
+                                          double value);
+
+/*
+ Message of the error from the last call on `session`, null after a call
+ that succeeded.
+
+ # Safety
+
+ `session` must be a live session.
+ */
+const char *calc_session_error_message(const struct CalcSession *session);
+
+/*
+ Byte offsets in the expression of what the last syntax error is about,
+ `*start == *end` at its end. Returns false, leaving both alone, when the
+ last call had no such error.
+
+ # Safety
+
+ `session` must be a live session, `start` and `end` must point to
+ writable `size_t`s.
+ */
+bool calc_session_error_span(const struct CalcSession *session, size_t *start, size_t *end);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* CALCULATOR_H */
@@ -0,0 +1,241 @@
+//! C interface to the calculator, for programs in other languages. The
+//! header is `include/calculator.h`, generated from this file on build.
+//!
+//! Strings go in as NUL terminated UTF-8. Strings handed out belong to the
+//! session and stay valid until its next call that changes it.
+
+use calculator::{eval, is_variable_name, Calculator, CalculatorError, Value};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Outcome of a call. The numbers are part of the interface and stay as
+/// they are.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalcStatus {
+    Ok = 0,
+    SyntaxError = 1,
+    RuntimeError = 2,
+    /// A null pointer, a string that is not UTF-8 or a bad variable name.
+    InvalidArgument = 3,
+    /// A bug in the calculator, the session is still usable.
+    InternalError = 4,
+}
+
+/// Variables, earlier results and the last error. Create it with
+/// `calc_session_new` and release it with `calc_session_free`.
+pub struct CalcSession {
+    calculator: Calculator,
+    error: Option<CString>,
+    span: Option<(usize, usize)>,
+}
+
+impl CalcSession {
+    fn fail(&mut self, status: CalcStatus, message: &str) -> CalcStatus {
+        // An interior NUL would cut the message short anyway.
+        self.error = Some(CString::new(message.replace('\0', " ")).unwrap_or_default());
+        self.span = None;
+        status
+    }
+}
+
+/// Evaluates `expression` without variables and stores the result in
+/// `*value`, which is left alone on errors.
+///
+/// # Safety
+///
+/// `expression` must be a NUL terminated string and `value` must point to a
+/// writable double, or either may be null.
+#[no_mangle]
+pub unsafe extern "C" fn calc_eval(expression: *const c_char, value: *mut f64) -> CalcStatus {
+    let expression = match to_str(expression) {
+        Some(expression) => expression,
+        None => return CalcStatus::InvalidArgument,
+    };
+    match catch_unwind(|| eval(expression)) {
+        Ok(Ok(result)) => {
+            if let Some(value) = value.as_mut() {
+                *value = result;
+            }
+            CalcStatus::Ok
+        }
+        Ok(Err(error)) => status(&error),
+        Err(_) => CalcStatus::InternalError,
+    }
+}
+
+/// A new session, to be released with `calc_session_free`.
+#[no_mangle]
+pub extern "C" fn calc_session_new() -> *mut CalcSession {
+    Box::into_raw(Box::new(CalcSession {
+        calculator: Calculator::default(),
+        error: None,
+        span: None,
+    }))
+}
+
+/// Releases `session`, null is ignored.
+///
+/// # Safety
+///
+/// `session` must come from `calc_session_new` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn calc_session_free(session: *mut CalcSession) {
+    if !session.is_null() {
+        drop(Box::from_raw(session));
+    }
+}
+
+/// Evaluates `expression` in `session` and stores the result in `*value`.
+/// `name = expression` also stores the result as a variable and `ans` is the
+/// last result, like on the page. A result that is a list or a matrix has
+/// no place in a double, it is a runtime error that leaves the session as it
+/// was.
+///
+/// # Safety
+///
+/// `session` must be a live session, `expression` a NUL terminated string
+/// and `value` must point to a writable double or be null.
+#[no_mangle]
+pub unsafe extern "C" fn calc_session_eval(
+    session: *mut CalcSession,
+    expression: *const c_char,
+    value: *mut f64,
+) -> CalcStatus {
+    let session = match session.as_mut() {
+        Some(session) => session,
+        None => return CalcStatus::InvalidArgument,
+    };
+    let expression = match to_str(expression) {
+        Some(expression) => expression,
+        None => {
+            return session.fail(
+                CalcStatus::InvalidArgument,
+                "expression is null or not UTF-8",
+            )
+        }
+    };
+    session.calculator.expression = expression.to_string();
+    let calculator = AssertUnwindSafe(&mut session.calculator);
+    match catch_unwind(move || {
+        let calculator = calculator.0;
+        if let Ok(result) = calculator.preview() {
+            result.number()?;
+        }
+        calculator.eval(timestamp()).and_then(Value::number)
+    }) {
+        Ok(Ok(result)) => {
+            if let Some(value) = value.as_mut() {
+                *value = result;
+            }
+            session.error = None;
+            session.span = None;
+            CalcStatus::Ok
+        }
+        Ok(Err(error)) => {
+            let status = session.fail(status(&error), &error.to_string());
+            session.span = error.span(expression).map(|span| (span.start, span.end));
+            status
+        }
+        Err(_) => session.fail(CalcStatus::InternalError, "internal error"),
+    }
+}
+
+/// Sets the variable `name` in `session`, replacing an earlier value. Names
+/// that could not be typed or would hide a built-in like `pi` are an
+/// invalid argument.
+///
+/// # Safety
+///
+/// `session` must be a live session and `name` a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn calc_session_set_variable(
+    session: *mut CalcSession,
+    name: *const c_char,
+    value: f64,
+) -> CalcStatus {
+    let session = match session.as_mut() {
+        Some(session) => session,
+        None => return CalcStatus::InvalidArgument,
+    };
+    let name = match to_str(name) {
+        Some(name) if is_variable_name(name) => name,
+        _ => {
+            return session.fail(
+                CalcStatus::InvalidArgument,
+                "variable names are a letter and letters or digits, not a built-in name",
+            )
+        }
+    };
+    // A list stored under the same name by `calc_session_eval` would shadow it.
+    session.calculator.lists.remove(name);
+    session.calculator.variables.insert(name.to_string(), value);
+    session.error = None;
+    session.span = None;
+    CalcStatus::Ok
+}
+
+/// Message of the error from the last call on `session`, null after a call
+/// that succeeded.
+///
+/// # Safety
+///
+/// `session` must be a live session.
+#[no_mangle]
+pub unsafe extern "C" fn calc_session_error_message(session: *const CalcSession) -> *const c_char {
+    match session.as_ref().and_then(|session| session.error.as_ref()) {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// Byte offsets in the expression of what the last syntax error is about,
+/// `*start == *end` at its end. Returns false, leaving both alone, when the
+/// last call had no such error.
+///
+/// # Safety
+///
+/// `session` must be a live session, `start` and `end` must point to
+/// writable `size_t`s.
+#[no_mangle]
+pub unsafe extern "C" fn calc_session_error_span(
+    session: *const CalcSession,
+    start: *mut usize,
+    end: *mut usize,
+) -> bool {
+    match (
+        session.as_ref().and_then(|session| session.span),
+        start.as_mut(),
+        end.as_mut(),
+    ) {
+        (Some(span), Some(start), Some(end)) => {
+            *start = span.0;
+            *end = span.1;
+            true
+        }
+        _ => false,
+    }
+}
+
+fn status(error: &CalculatorError) -> CalcStatus {
+    match error {
+        CalculatorError::Syntax(_, _) => CalcStatus::SyntaxError,
+        CalculatorError::Runtime(_) => CalcStatus::RuntimeError,
+    }
+}
+
+unsafe fn to_str<'a>(string: *const c_char) -> Option<&'a str> {
+    if string.is_null() {
+        return None;
+    }
+    CStr::from_ptr(string).to_str().ok()
+}
+
+fn timestamp() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_millis() as f64)
+}
@@ -0,0 +1,47 @@
+// Builds `session.c` against the static library and runs it.
+
+#![cfg(unix)]
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// `cargo test` builds the crate as an rlib only, so the static library is
+// built here, in a target directory of its own to stay clear of the lock on
+// the one running the tests.
+fn build_library() -> PathBuf {
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("capi");
+    let status = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+        .args(["build", "--lib", "--manifest-path"])
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .expect("cannot run cargo");
+    assert!(status.success(), "cannot build the static library");
+    target_dir.join("debug")
+}
+
+#[test]
+fn c_program() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let library_dir = build_library();
+    let library = library_dir.join("libcalculator_capi.a");
+    let program = library_dir.join("calculator_capi_session");
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+        .arg(crate_dir.join("tests").join("session.c"))
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg(&library)
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&program)
+        .status()
+        .expect("cannot run the C compiler");
+    assert!(status.success(), "session.c does not compile");
+    let output = Command::new(&program).output().unwrap();
+    assert!(
+        output.status.success(),
+        "session.c failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,71 @@
+/* Exercises the C interface, run by `cargo test -p calculator_capi`. */
+
+#include <math.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "calculator.h"
+
+static int failures = 0;
+
+#define CHECK(condition)                                                   \
+    do {                                                                   \
+        if (!(condition)) {                                                \
+            fprintf(stderr, "%s:%d: %s\n", __FILE__, __LINE__, #condition); \
+            failures++;                                                    \
+        }                                                                  \
+    } while (0)
+
+static int near(double a, double b) {
+    return fabs(a - b) < 1e-9;
+}
+
+int main(void) {
+    double value = 0;
+
+    CHECK(calc_eval("1 + 2 * 3", &value) == CALC_STATUS_OK);
+    CHECK(near(value, 7));
+    CHECK(calc_eval("1 +", &value) == CALC_STATUS_SYNTAX_ERROR);
+    CHECK(near(value, 7));
+    CHECK(calc_eval(NULL, &value) == CALC_STATUS_INVALID_ARGUMENT);
+
+    CalcSession *session = calc_session_new();
+    CHECK(session != NULL);
+
+    CHECK(calc_session_set_variable(session, "x", 4) == CALC_STATUS_OK);
+    CHECK(calc_session_eval(session, "sqrt(x) + 1", &value) == CALC_STATUS_OK);
+    CHECK(near(value, 3));
+    CHECK(calc_session_error_message(session) == NULL);
+
+    CHECK(calc_session_eval(session, "y = ans * 2", &value) == CALC_STATUS_OK);
+    CHECK(calc_session_eval(session, "y + x", &value) == CALC_STATUS_OK);
+    CHECK(near(value, 10));
+
+    size_t start = 0, end = 0;
+    CHECK(calc_session_eval(session, "2 * $", &value) == CALC_STATUS_SYNTAX_ERROR);
+    CHECK(calc_session_error_message(session) != NULL);
+    CHECK(calc_session_error_span(session, &start, &end));
+    CHECK(start == 4 && end == 5);
+
+    CHECK(calc_session_eval(session, "nosuch(1)", &value) == CALC_STATUS_RUNTIME_ERROR);
+    CHECK(calc_session_error_message(session) != NULL);
+    CHECK(!calc_session_error_span(session, &start, &end));
+
+    CHECK(calc_session_eval(session, "v = [1, 2]", &value) == CALC_STATUS_RUNTIME_ERROR);
+    CHECK(calc_session_eval(session, "v", &value) == CALC_STATUS_RUNTIME_ERROR);
+    CHECK(calc_session_set_variable(session, "v", 5) == CALC_STATUS_OK);
+    CHECK(calc_session_eval(session, "v + 1", &value) == CALC_STATUS_OK);
+    CHECK(near(value, 6));
+
+    CHECK(calc_session_set_variable(session, "2x", 1) == CALC_STATUS_INVALID_ARGUMENT);
+    CHECK(calc_session_set_variable(session, "pi", 3) == CALC_STATUS_INVALID_ARGUMENT);
+    CHECK(calc_session_error_message(session) != NULL);
+
+    calc_session_free(session);
+    calc_session_free(NULL);
+
+    if (failures == 0) {
+        printf("ok\n");
+    }
+    return failures == 0 ? 0 : 1;
+}
@@ -0,0 +1,374 @@
+//! `calc`, the calculator in a terminal.
+//!
+//! Without arguments it starts an interactive session. Otherwise the
+//! arguments are evaluated as one expression, the result is printed and the
+//! exit status tells how it went: 0 for a result, 1 for a syntax error and
+//! 2 for a runtime error.
+//!
+//! `calc --csv FILE NAME=EXPRESSION...` adds a column for each expression to
+//! a CSV, see [`csv`].
+//!
+//! Leading `--radix`, `--word` and `--unsigned` options apply to all of
+//! these, see [`Options`].
+
+use calculator::{
+    eval_csv, BatchError, Calculator, CalculatorError, Settings, Value, WordSize, CONSTANTS,
+    FUNCTIONS, WORD_BITS,
+};
+use rustyline::error::ReadlineError;
+use rustyline::Editor;
+use std::collections::HashMap;
+use std::env;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PROMPT: &str = "> ";
+const CONTINUATION: &str = ". ";
+const HISTORY_FILE: &str = ".calc_history";
+
+const EXIT_SYNTAX: i32 = 1;
+const EXIT_RUNTIME: i32 = 2;
+const EXIT_USAGE: i32 = 64;
+const EXIT_IO: i32 = 74;
+
+const USAGE: &str = "usage: calc [OPTION...] [EXPRESSION...]
+       calc [OPTION...] --csv FILE NAME=EXPRESSION...
+
+Evaluates EXPRESSION, or starts an interactive session when there is none.
+Exits with 1 on a syntax error and 2 on a runtime error.
+
+  --radix N    prints whole results in base N, from 2 to 36, like 0xFF for 16
+  --word BITS  wraps every result to a signed integer of 8, 16 or 32 bits
+  --unsigned   makes the --word integer unsigned
+
+With --csv, reads FILE, or standard input for -, and writes it with a column
+NAME added for each EXPRESSION, computed from the columns of the row. Rows
+where an expression fails get an empty cell and a message on standard error,
+the exit status is then 2.";
+
+const HELP: &str = "Type an expression to evaluate it, `name = expression` to store a variable.
+`ans` is the last result, `ans1` the one before it and so on. Input with
+unclosed parentheses or a trailing operator continues on the next line, as
+does a line ending in `\\`. An empty line evaluates it as it is.
+
+:help    this text
+:vars    variables and previous results
+:funcs   functions and constants
+:quit    leave, as does Ctrl+D";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (options, args) = match Options::parse(&args) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(EXIT_USAGE);
+        }
+    };
+    let status = match args.first().map(String::as_str) {
+        None => repl(&options),
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            0
+        }
+        Some("--csv") => csv(&args[1..], &options),
+        Some(option) if option.starts_with("--") => {
+            eprintln!("unknown option {}\n{}", option, USAGE);
+            EXIT_USAGE
+        }
+        Some(_) => once(&args.join(" "), &options),
+    };
+    process::exit(status);
+}
+
+/// How results are computed and printed: in `radix`, wrapped to `word_size`
+/// when it is set. CSV cells stay decimal.
+struct Options {
+    radix: u32,
+    word_size: Option<WordSize>,
+}
+
+impl Options {
+    // Reads the options at the start of `args`, returning the arguments after
+    // them.
+    fn parse(mut args: &[String]) -> Result<(Self, &[String]), String> {
+        let mut radix = 10;
+        let mut bits = None;
+        let mut signed = true;
+        loop {
+            match args {
+                [option, value, rest @ ..] if option == "--radix" => {
+                    radix = value
+                        .parse()
+                        .ok()
+                        .filter(|radix| (2..=36).contains(radix))
+                        .ok_or_else(|| format!("--radix takes 2 to 36, not {}", value))?;
+                    args = rest;
+                }
+                [option, value, rest @ ..] if option == "--word" => {
+                    bits = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|bits| WORD_BITS.contains(bits))
+                            .ok_or_else(|| format!("--word takes 8, 16 or 32, not {}", value))?,
+                    );
+                    args = rest;
+                }
+                [option, rest @ ..] if option == "--unsigned" => {
+                    signed = false;
+                    args = rest;
+                }
+                [option] if option == "--radix" || option == "--word" => {
+                    return Err(format!("{} needs a value", option));
+                }
+                _ => break,
+            }
+        }
+        if !signed && bits.is_none() {
+            return Err("--unsigned needs --word".to_string());
+        }
+        let word_size = bits.and_then(|bits| WordSize::new(bits, signed));
+        Ok((Options { radix, word_size }, args))
+    }
+
+    fn calculator(&self) -> Calculator {
+        let mut calculator = Calculator::default();
+        calculator.formatter.radix = self.radix;
+        calculator.set_word_size(self.word_size);
+        calculator
+    }
+}
+
+fn once(expression: &str, options: &Options) -> i32 {
+    let mut calculator = options.calculator();
+    calculator.expression = expression.to_string();
+    match calculator.eval(timestamp()) {
+        Ok(_) => {
+            println!("{}", calculator.display());
+            0
+        }
+        Err(error) => {
+            report(expression, &error);
+            exit_status(&error)
+        }
+    }
+}
+
+// Adds the columns `NAME=EXPRESSION` in `args[1..]` to the CSV in `args[0]`.
+fn csv(args: &[String], options: &Options) -> i32 {
+    if args.len() < 2 {
+        eprintln!("{}", USAGE);
+        return EXIT_USAGE;
+    }
+    let mut expressions = Vec::new();
+    for arg in args.iter().skip(1) {
+        match arg.find('=') {
+            Some(i) if i > 0 => expressions.push((arg[..i].trim(), &arg[i + 1..])),
+            _ => {
+                eprintln!("expected NAME=EXPRESSION, got {}\n{}", arg, USAGE);
+                return EXIT_USAGE;
+            }
+        }
+    }
+    let input: Box<dyn Read> = match args[0].as_str() {
+        "-" => Box::new(io::stdin()),
+        path => match File::open(path) {
+            Ok(file) => Box::new(file),
+            Err(error) => {
+                eprintln!("cannot open {}: {}", path, error);
+                return EXIT_IO;
+            }
+        },
+    };
+    match eval_csv(
+        input,
+        io::stdout().lock(),
+        &expressions,
+        &Settings {
+            word_size: options.word_size,
+            ..Settings::default()
+        },
+        &HashMap::new(),
+    ) {
+        Ok(errors) if errors.is_empty() => 0,
+        Ok(errors) => {
+            for error in &errors {
+                eprintln!("row {}, {}: {}", error.row, error.column, error.error);
+            }
+            EXIT_RUNTIME
+        }
+        Err(BatchError::Expression { name, error }) => {
+            let (_, expression) = expressions.iter().find(|(n, _)| *n == name).unwrap();
+            eprintln!("in {}:", name);
+            report(expression, &error);
+            exit_status(&error)
+        }
+        Err(error @ BatchError::DuplicateColumn(_)) => {
+            eprintln!("{}\n{}", error, USAGE);
+            EXIT_USAGE
+        }
+        Err(error @ BatchError::DuplicateHeader(_)) => {
+            eprintln!("{}", error);
+            EXIT_IO
+        }
+        Err(BatchError::Csv(error)) => {
+            eprintln!("{}", error);
+            EXIT_IO
+        }
+    }
+}
+
+fn exit_status(error: &CalculatorError) -> i32 {
+    match error {
+        CalculatorError::Syntax(_, _) => EXIT_SYNTAX,
+        CalculatorError::Runtime(_) => EXIT_RUNTIME,
+    }
+}
+
+// Prints the error, with a caret under the offending part when it has one.
+fn report(expression: &str, error: &CalculatorError) {
+    if let Some(span) = error.span(expression) {
+        let line_start = expression[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = expression[span.start..]
+            .find('\n')
+            .map_or(expression.len(), |i| span.start + i);
+        let indent = expression[line_start..span.start].chars().count();
+        let width = expression[span.clone()].chars().count().max(1);
+        eprintln!("{}", &expression[line_start..line_end]);
+        eprintln!("{}{}", " ".repeat(indent), "^".repeat(width));
+    }
+    eprintln!("{}", error);
+}
+
+fn repl(options: &Options) -> i32 {
+    let mut editor = Editor::<()>::new();
+    let history = history_path();
+    if let Some(path) = &history {
+        let _ = editor.load_history(path);
+    }
+    let mut calculator = options.calculator();
+    let mut buffer = String::new();
+    loop {
+        let prompt = if buffer.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION
+        };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            // Ctrl+C drops the input so far but keeps the session.
+            Err(ReadlineError::Interrupted) => {
+                buffer.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(error) => {
+                eprintln!("{}", error);
+                break;
+            }
+        };
+        if buffer.is_empty() {
+            let command = line.trim();
+            if command.is_empty() {
+                continue;
+            }
+            if command.starts_with(':') {
+                editor.add_history_entry(command);
+                if !run_command(command, &calculator) {
+                    break;
+                }
+                continue;
+            }
+        }
+        let (line, more) = match line.strip_suffix('\\') {
+            Some(line) => (line, true),
+            None => (line.as_str(), false),
+        };
+        if !buffer.is_empty() {
+            buffer.push('\n');
+        }
+        buffer.push_str(line);
+        if more {
+            continue;
+        }
+        calculator.expression = buffer.clone();
+        if let Err(error) = calculator.preview() {
+            if error.is_incomplete(&buffer) && !line.trim().is_empty() {
+                continue;
+            }
+        }
+        editor.add_history_entry(buffer.replace('\n', " "));
+        match calculator.eval(timestamp()) {
+            Ok(_) => println!("{}", calculator.display()),
+            Err(error) => report(&buffer, &error),
+        }
+        buffer.clear();
+    }
+    if let Some(path) = &history {
+        if let Err(error) = editor.save_history(path) {
+            eprintln!("cannot save history to {}: {}", path.display(), error);
+        }
+    }
+    0
+}
+
+// Runs a `:command`, returning false when the session should end.
+fn run_command(command: &str, calculator: &Calculator) -> bool {
+    match command {
+        ":help" | ":h" | ":?" => println!("{}", HELP),
+        ":vars" | ":v" => {
+            let mut variables: Vec<(&String, &f64)> = calculator.variables.iter().collect();
+            variables.sort_by(|a, b| a.0.cmp(b.0));
+            let answers = calculator.answers();
+            let mut answers: Vec<(&String, &f64)> = answers.iter().collect();
+            answers.sort_by_key(|(name, _)| (name.len(), name.to_string()));
+            let mut lists: Vec<(&String, &Value)> = calculator.lists.iter().collect();
+            lists.sort_by(|a, b| a.0.cmp(b.0));
+            if variables.is_empty() && answers.is_empty() && lists.is_empty() {
+                println!("no variables yet");
+            }
+            for (name, value) in variables.into_iter().chain(answers) {
+                println!("{} = {}", name, calculator.formatter.format(*value));
+            }
+            for (name, value) in lists {
+                println!("{} = {}", name, calculator.formatter.format_value(value));
+            }
+        }
+        ":funcs" | ":f" => {
+            let width = FUNCTIONS
+                .iter()
+                .map(|(_, signature, _)| signature.len())
+                .max()
+                .unwrap_or(0);
+            for (_, signature, doc) in FUNCTIONS.iter() {
+                println!("{:width$}  {}", signature, doc, width = width);
+            }
+            println!();
+            for (name, doc) in CONSTANTS.iter() {
+                println!("{:width$}  {}", name, doc, width = width);
+            }
+        }
+        ":quit" | ":q" | ":exit" => return false,
+        _ => eprintln!("unknown command {}, try :help", command),
+    }
+    true
+}
+
+fn history_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("CALC_HISTORY") {
+        return Some(PathBuf::from(path));
+    }
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+    Some(PathBuf::from(home).join(HISTORY_FILE))
+}
+
+// Milliseconds since the Unix epoch, like the web page records.
+fn timestamp() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |elapsed| elapsed.as_millis() as f64)
+}
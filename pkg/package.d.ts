@@ -3,6 +3,100 @@
 /**
 */
 export function start(): void;
+/**
+* Evaluates `expression` on its own, with `options.variables` as the only
+* names besides the built-in ones. Throws a `TypeError` for bad options.
+* @param {string} expression
+* @param {EvaluateOptions | undefined} options
+* @returns {EvaluateResult}
+*/
+export function evaluate(expression: string, options?: EvaluateOptions): EvaluateResult;
+/**
+* Splits `expression` into tokens, anything the lexer rejects ends up in one
+* trailing `invalid` token.
+* @param {string} expression
+* @param {EvaluateOptions | undefined} options
+* @returns {Token[]}
+*/
+export function tokenize(expression: string, options?: EvaluateOptions): Token[];
+
+export interface EvaluateOptions {
+  /** Unit of trigonometric functions, "radians" by default. */
+  angle?: "radians" | "degrees";
+  /** Decimal mark and argument separator, "en-US" by default. */
+  locale?: "en-US" | "de-DE" | "fr-FR" | "de-CH";
+  /** Wraps every intermediate result to an integer of this size. */
+  wordSize?: { bits: 8 | 16 | 32; signed?: boolean };
+  variables?: Record<string, number>;
+}
+
+export interface CalculatorError {
+  kind: "syntax" | "runtime";
+  message: string;
+  /** Where a syntax error is, missing for runtime errors. */
+  start?: number;
+  end?: number;
+  /** The expression ended too early, like `1+`. */
+  incomplete: boolean;
+}
+
+/** A number, a list, or a matrix as its rows. */
+export type CalculatorValue = number | number[] | number[][];
+
+export type EvaluateResult =
+  | { ok: true; value: CalculatorValue; text: string }
+  | { ok: false; error: CalculatorError };
+
+export interface Token {
+  kind: "number" | "constant" | "variable" | "function" | "operator" | "paren" | "separator" | "unknown" | "invalid";
+  text: string;
+  start: number;
+  end: number;
+}
+
+/**
+* A calculator that remembers variables and earlier results between
+* evaluations, like the page does: `x = 2` stores `x`, `ans` is the last
+* result.
+*/
+export class Session {
+  free(): void;
+/**
+* @param {EvaluateOptions | undefined} options
+*/
+  constructor(options?: EvaluateOptions);
+/**
+* @param {string} expression
+* @returns {EvaluateResult}
+*/
+  evaluate(expression: string): EvaluateResult;
+/**
+* @param {string} expression
+* @returns {Token[]}
+*/
+  tokenize(expression: string): Token[];
+/**
+* Throws a `TypeError` for a name that could not be typed or would hide
+* a built-in.
+* @param {string} name
+* @param {number} value
+*/
+  setVariable(name: string, value: number): void;
+/**
+* @param {string} name
+* @returns {boolean}
+*/
+  removeVariable(name: string): boolean;
+/**
+* The stored variables that are numbers, without the `ans` results.
+* @returns {Record<string, number>}
+*/
+  variables(): Record<string, number>;
+/**
+* Forgets the earlier results, the variables stay.
+*/
+  clearHistory(): void;
+}
 
 export type InitInput = RequestInfo | URL | Response | BufferSource | WebAssembly.Module;
 
@@ -1,51 +1,864 @@
-use crate::calculator;
+use calculator::{
+    recognize, Angle, Calculator, CalculatorError, Completion, Formatter, Locale, Notation, Value,
+    WordSize, LOCALES, WORD_BITS,
+};
 
 use seed::{prelude::*, *};
 
-fn init(_: Url, _: &mut impl Orders<Msg>) -> Model {
-    Model::default()
+mod completions;
+mod highlight;
+mod keypad;
+mod plot;
+mod share;
+mod shortcuts;
+mod storage;
+
+use keypad::Key;
+
+const PREVIEW_DELAY: u32 = 250;
+const ERROR_ID: &str = "error";
+
+const NOTATIONS: [(&str, &str, Notation); 5] = [
+    ("auto", "Auto", Notation::Auto),
+    ("fixed", "Fixed", Notation::Fixed(2)),
+    ("scientific", "Scientific", Notation::Scientific),
+    ("engineering", "Engineering", Notation::Engineering),
+    ("significant", "Significant", Notation::Significant(6)),
+];
+
+// Value and label of the grouping choices, the empty value groups nothing.
+const THOUSANDS_SEPARATORS: [(&str, &str); 5] = [
+    ("", "1000"),
+    (",", "1,000"),
+    (".", "1.000"),
+    ("\u{202f}", "1 000"),
+    ("'", "1'000"),
+];
+
+const DECIMAL_MARKS: [(&str, &str); 2] = [(".", "0.5"), (",", "0,5")];
+
+const RADIXES: [(u32, &str); 4] = [(10, "dec"), (16, "hex"), (8, "oct"), (2, "bin")];
+
+// More digits than an `f64` holds are noise.
+const MAX_DIGITS: usize = 15;
+
+// The digits of the notations that take a count.
+fn digits(notation: Notation) -> Option<usize> {
+    match notation {
+        Notation::Fixed(digits) | Notation::Significant(digits) => Some(digits),
+        _ => None,
+    }
+}
+
+fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
+    let mut model = Model::default();
+    if let Some(session) = storage::load() {
+        session.apply(&mut model);
+    }
+    // A shared link wins over the stored session, but only fills in the input.
+    if share::restore(&url, &mut model.calculator) {
+        orders.send_msg(Msg::Preview);
+    }
+    orders.stream(streams::window_event(Ev::KeyDown, |event| {
+        shortcuts::global(event.unchecked_ref())
+    }));
+    model
+}
+
+#[derive(Default)]
+struct Model {
+    calculator: Calculator,
+    input: ElRef<web_sys::HtmlInputElement>,
+    overlay: ElRef<web_sys::HtmlElement>,
+    /// Byte offset of the caret in the expression.
+    cursor: usize,
+    completions: Vec<Completion>,
+    completion: usize,
+    preview: Option<Result<Value, CalculatorError>>,
+    preview_timer: Option<CmdHandle>,
+    shift: bool,
+    theme: Theme,
+    share_variables: bool,
+    plot: Option<plot::Plotter>,
+    /// History entry shown while walking it with the arrow keys.
+    recall: Option<usize>,
+    /// What was typed before the walk started.
+    draft: String,
+    /// Read out by screen readers whenever it changes.
+    announcement: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+enum Theme {
+    #[default]
+    Light,
+    Dark,
 }
 
-type Model = calculator::Calculator;
+impl Theme {
+    fn name(&self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+
+    fn from_name(name: &str) -> Self {
+        if name == "dark" {
+            Theme::Dark
+        } else {
+            Theme::Light
+        }
+    }
+}
 
 #[derive(Clone)]
 // `Msg` describes the different events you can modify state with.
 enum Msg {
     ExpressionChanged(String),
+    CursorMoved,
+    Complete,
+    SelectCompletion(usize),
+    AcceptCompletion(usize),
+    DismissCompletions,
+    Preview,
     Eval,
+    HistoryUp,
+    HistoryDown,
+    NotationChanged(String),
+    DigitsChanged(String),
+    ThousandsSeparatorChanged(String),
+    DecimalMarkChanged(String),
+    RadixChanged(String),
+    LocaleChanged(String),
+    AngleChanged(String),
+    WordSizeChanged(String),
+    ToggleSigned,
+    ToggleTheme,
+    Export,
+    Import(web_sys::HtmlInputElement),
+    Imported(String),
+    CopyLink,
+    ToggleShareVariables,
+    ToggleRecognize,
+    ReuseExpression(usize),
+    ReuseResult(usize),
+    DeleteEntry(usize),
+    ClearHistory,
+    Key(Key),
+    Plot(plot::Event),
+    DrawPlot,
 }
 
 // `update` describes how to handle each `Msg`.
-fn update(msg: Msg, model: &mut Model, _: &mut impl Orders<Msg>) {
+fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
+    let calculator = &mut model.calculator;
     match msg {
         Msg::ExpressionChanged(expression) => {
-            model.expression = expression;
+            calculator.expression = expression;
+            // Dropping the previous handle cancels its timeout.
+            model.preview_timer =
+                Some(orders.perform_cmd_with_handle(cmds::timeout(PREVIEW_DELAY, || Msg::Preview)));
+            orders.after_next_render(|_| Msg::Complete);
+        }
+        Msg::CursorMoved => {
+            model.cursor = cursor(&model.input, &calculator.expression);
+            // The overlay has no scrollbar of its own, it follows the input.
+            if let (Some(input), Some(overlay)) = (model.input.get(), model.overlay.get()) {
+                overlay.set_scroll_left(input.scroll_left());
+            }
+            return;
+        }
+        Msg::Complete => {
+            model.cursor = cursor(&model.input, &calculator.expression);
+            model.completions = calculator.complete(model.cursor);
+            model.completion = 0;
+            orders.send_msg(Msg::CursorMoved);
+            return;
+        }
+        Msg::SelectCompletion(index) => {
+            model.completion = index;
+            return;
+        }
+        Msg::AcceptCompletion(index) => {
+            if let Some(completion) = model.completions.get(index) {
+                let mut expression = calculator.expression.clone();
+                let text = completion.text();
+                expression.replace_range(completion.range.clone(), &text);
+                let cursor = completion.range.start + text.len();
+                model.completions.clear();
+                set_expression(&model.input, orders, expression, cursor);
+            }
+            return;
+        }
+        Msg::DismissCompletions => {
+            model.completions.clear();
+            return;
+        }
+        Msg::Preview => {
+            model.preview_timer = None;
+            model.preview = match calculator.plot() {
+                _ if calculator.expression.trim().is_empty() => None,
+                Some(plot) => plot.err().map(Err),
+                None => Some(calculator.preview()),
+            };
         }
         Msg::Eval => {
-            model.value = calculator::eval(&model.expression).unwrap();
+            model.preview_timer = None;
+            model.preview = None;
+            model.recall = None;
+            // Plots are drawn rather than evaluated and stay out of the history.
+            let result = match calculator.plot() {
+                Some(plot) => plot
+                    .and_then(|plot| plot::Plotter::new(calculator, plot))
+                    .map(|plotter| {
+                        orders.after_next_render(|_| Msg::DrawPlot);
+                        Some(plotter)
+                    }),
+                None => calculator.eval(js_sys::Date::now()).map(|_| None),
+            };
+            match result {
+                Ok(Some(plotter)) => {
+                    model.plot = Some(plotter);
+                    model.announcement = format!("plotted {}", calculator.expression);
+                    focus(&model.input, orders);
+                }
+                Ok(None) => {
+                    model.announcement =
+                        format!("{} equals {}", calculator.expression, calculator.display());
+                    focus(&model.input, orders);
+                }
+                Err(error) => {
+                    select_error(&model.input, &calculator.expression, &error);
+                    model.announcement = error.to_string();
+                    calculator.result = Some(Err(error));
+                }
+            }
+            share::url(calculator, model.share_variables).go_and_replace();
+        }
+        Msg::HistoryUp => {
+            let index = match model.recall {
+                Some(index) => index.saturating_sub(1),
+                None if calculator.history.is_empty() => return,
+                None => {
+                    model.draft = calculator.expression.clone();
+                    calculator.history.len() - 1
+                }
+            };
+            model.recall = Some(index);
+            let expression = calculator.history[index].expression.clone();
+            let cursor = expression.len();
+            set_expression(&model.input, orders, expression, cursor);
+        }
+        Msg::HistoryDown => {
+            let expression = match model.recall {
+                None => return,
+                Some(index) if index + 1 < calculator.history.len() => {
+                    model.recall = Some(index + 1);
+                    calculator.history[index + 1].expression.clone()
+                }
+                Some(_) => {
+                    model.recall = None;
+                    std::mem::take(&mut model.draft)
+                }
+            };
+            let cursor = expression.len();
+            set_expression(&model.input, orders, expression, cursor);
+        }
+        Msg::NotationChanged(notation) => {
+            let notation = NOTATIONS
+                .iter()
+                .find(|(value, _, _)| *value == notation)
+                .map_or(Notation::Auto, |&(_, _, notation)| notation);
+            // Switching between fixed and significant keeps the digits.
+            calculator.formatter.notation = match (notation, digits(calculator.formatter.notation))
+            {
+                (Notation::Fixed(_), Some(digits)) => Notation::Fixed(digits),
+                (Notation::Significant(_), Some(digits)) => Notation::Significant(digits.max(1)),
+                (notation, _) => notation,
+            };
+        }
+        Msg::DigitsChanged(text) => {
+            if let Ok(digits) = text.trim().parse::<usize>() {
+                let digits = digits.min(MAX_DIGITS);
+                calculator.formatter.notation = match calculator.formatter.notation {
+                    Notation::Fixed(_) => Notation::Fixed(digits),
+                    Notation::Significant(_) => Notation::Significant(digits.max(1)),
+                    notation => notation,
+                };
+            }
+        }
+        Msg::ThousandsSeparatorChanged(separator) => {
+            calculator.formatter.thousands_separator = separator.chars().next();
+        }
+        Msg::DecimalMarkChanged(mark) => {
+            if let Some(mark) = mark.chars().next() {
+                calculator.formatter.decimal_mark = mark;
+            }
+        }
+        Msg::RadixChanged(radix) => {
+            calculator.formatter.radix = radix.parse().unwrap_or(10);
+        }
+        Msg::LocaleChanged(tag) => {
+            let locale = Locale::from_tag(&tag).unwrap_or_default();
+            calculator.settings.locale = locale;
+            calculator.formatter = Formatter {
+                radix: calculator.formatter.radix,
+                word_size: calculator.formatter.word_size,
+                ..locale.formatter(calculator.formatter.notation)
+            };
+        }
+        Msg::AngleChanged(angle) => {
+            calculator.settings.angle = if angle == "deg" {
+                Angle::Degrees
+            } else {
+                Angle::Radians
+            };
+        }
+        Msg::WordSizeChanged(bits) => {
+            let signed = calculator
+                .settings
+                .word_size
+                .map(|word| word.signed())
+                .unwrap_or(true);
+            let word_size = bits
+                .parse()
+                .ok()
+                .and_then(|bits| WordSize::new(bits, signed));
+            calculator.set_word_size(word_size);
+        }
+        Msg::ToggleSigned => {
+            let word_size = calculator
+                .settings
+                .word_size
+                .and_then(|word| WordSize::new(word.bits(), !word.signed()));
+            calculator.set_word_size(word_size);
+        }
+        Msg::ToggleTheme => {
+            model.theme = match model.theme {
+                Theme::Light => Theme::Dark,
+                Theme::Dark => Theme::Light,
+            };
+        }
+        Msg::Export => {
+            storage::export(model);
+        }
+        Msg::Import(input) => {
+            orders.perform_cmd(async move { storage::read_file(input).await.map(Msg::Imported) });
+        }
+        Msg::Imported(json) => match storage::Session::from_json(&json) {
+            Some(session) => session.apply(model),
+            None => error!("not a calculator session"),
+        },
+        Msg::CopyLink => {
+            if let Err(error) = share::copy(&share::url(calculator, model.share_variables)) {
+                error!("cannot copy link", error);
+            }
+        }
+        Msg::ToggleShareVariables => {
+            model.share_variables = !model.share_variables;
+        }
+        Msg::ToggleRecognize => {
+            calculator.recognize = !calculator.recognize;
+        }
+        Msg::ReuseExpression(index) => {
+            if let Some(entry) = calculator.history.get(index) {
+                let expression = entry.expression.clone();
+                let cursor = expression.len();
+                set_expression(&model.input, orders, expression, cursor);
+            }
+        }
+        Msg::ReuseResult(index) => {
+            if let Some(Ok(value)) = calculator.history.get(index).map(|entry| &entry.result) {
+                let value = calculator
+                    .settings
+                    .locale
+                    .formatter(Notation::Auto)
+                    .format_value(value);
+                let expression = format!("{}{}", calculator.expression, value);
+                let cursor = expression.len();
+                set_expression(&model.input, orders, expression, cursor);
+            }
+        }
+        Msg::DeleteEntry(index) => {
+            calculator.remove_entry(index);
+            model.recall = None;
+            model.announcement = "entry deleted".to_string();
+            focus(&model.input, orders);
+        }
+        Msg::ClearHistory => {
+            calculator.clear_history();
+            model.recall = None;
+            model.announcement = "history cleared".to_string();
+            focus(&model.input, orders);
+        }
+        Msg::Key(key) => {
+            let mut expression = calculator.expression.clone();
+            let cursor = cursor(&model.input, &expression);
+            let locale = calculator.settings.locale;
+            let cursor = match key {
+                Key::Insert(text) => keypad::insert(&mut expression, cursor, text),
+                Key::Decimal => {
+                    keypad::insert(&mut expression, cursor, &locale.decimal_mark().to_string())
+                }
+                Key::Separator => keypad::insert(
+                    &mut expression,
+                    cursor,
+                    &locale.argument_separator().to_string(),
+                ),
+                Key::Backspace => keypad::backspace(&mut expression, cursor),
+                Key::Clear => {
+                    expression.clear();
+                    0
+                }
+                Key::Shift => {
+                    model.shift = !model.shift;
+                    return;
+                }
+                Key::Eval => {
+                    orders.send_msg(Msg::Eval);
+                    return;
+                }
+            };
+            model.shift = false;
+            set_expression(&model.input, orders, expression, cursor);
+        }
+        Msg::Plot(plot::Event::Close) => {
+            model.plot = None;
+        }
+        Msg::Plot(event) => {
+            if let Some(plotter) = &mut model.plot {
+                plotter.update(event);
+                orders.after_next_render(|_| Msg::DrawPlot);
+            }
+            // Pointer moves come too often to store the session each time.
+            return;
+        }
+        Msg::DrawPlot => {
+            if let Some(plotter) = &model.plot {
+                plotter.draw(&calculator.formatter);
+            }
+            return;
+        }
+    }
+    storage::save(model);
+}
+
+// Replaces the expression as if typed, leaving the caret at the byte offset `cursor`.
+fn set_expression(
+    input: &ElRef<web_sys::HtmlInputElement>,
+    orders: &mut impl Orders<Msg>,
+    expression: String,
+    cursor: usize,
+) {
+    let position = utf16_index(&expression, cursor);
+    let input = input.clone();
+    orders
+        .send_msg(Msg::ExpressionChanged(expression))
+        .after_next_render(move |_| {
+            if let Some(input) = input.get() {
+                let _ = input.focus();
+                let _ = input.set_selection_range(position, position);
+            }
+            Msg::CursorMoved
+        });
+}
+
+// Moves the focus back to the input once the buttons that had it are redrawn.
+fn focus(input: &ElRef<web_sys::HtmlInputElement>, orders: &mut impl Orders<Msg>) {
+    let input = input.clone();
+    orders.after_next_render(move |_| {
+        if let Some(input) = input.get() {
+            let _ = input.focus();
+        }
+    });
+}
+
+// Byte offset of the caret, the end when the input is not rendered yet.
+fn cursor(input: &ElRef<web_sys::HtmlInputElement>, expression: &str) -> usize {
+    input
+        .get()
+        .and_then(|input| input.selection_start().ok().flatten())
+        .map_or(expression.len(), |i| byte_index(expression, i))
+}
+
+fn utf16_index(expression: &str, byte_index: usize) -> u32 {
+    expression[..byte_index].encode_utf16().count() as u32
+}
+
+fn byte_index(expression: &str, utf16_index: u32) -> usize {
+    let mut units = 0;
+    for (i, c) in expression.char_indices() {
+        if units >= utf16_index as usize {
+            return i;
         }
+        units += c.len_utf16();
+    }
+    expression.len()
+}
+
+// Selects the offending characters so they stand out inside the input.
+fn select_error(
+    input: &ElRef<web_sys::HtmlInputElement>,
+    expression: &str,
+    error: &CalculatorError,
+) {
+    if let (Some(input), Some(span)) = (input.get(), error.span(expression)) {
+        let _ = input.focus();
+        let _ = input.set_selection_range(
+            utf16_index(expression, span.start),
+            utf16_index(expression, span.end),
+        );
     }
 }
 
 #[allow(clippy::trivially_copy_pass_by_ref)]
 // `view` describes what to display.
 fn view(model: &Model) -> Node<Msg> {
+    let calculator = &model.calculator;
+    let error = match &calculator.result {
+        Some(Err(error)) => Some(error),
+        _ => None,
+    };
+    let open = !model.completions.is_empty();
+    let (selected, count) = (model.completion, model.completions.len());
     div![
-        C!["counter"],
-        input![
-            attrs! {
-                At::Value => model.expression
+        C!["counter", model.theme.name()],
+        div![
+            C!["editor"],
+            highlight::view(calculator, model.cursor, &model.overlay),
+            input![
+                el_ref(&model.input),
+                C![IF!(error.is_some() => "invalid")],
+                attrs! {
+                    At::Value => calculator.expression,
+                    At::AriaLabel => "expression",
+                    At::AriaInvalid => error.is_some().as_at_value(),
+                    At::AriaDescribedBy => if error.is_some() { AtValue::Some(ERROR_ID.to_string()) } else { AtValue::Ignored },
+                    At::SpellCheck => "false",
+                    At::from("role") => "combobox",
+                    At::AriaExpanded => open.as_at_value(),
+                    At::AriaControls => completions::LISTBOX_ID,
+                    At::AriaActiveDescendant => if open {
+                        AtValue::Some(completions::option_id(selected))
+                    } else {
+                        AtValue::Ignored
+                    }
+                },
+                input_ev(Ev::Input, Msg::ExpressionChanged),
+                keyboard_ev(Ev::KeyDown, move |event| shortcuts::input(
+                    &event, count, selected
+                )),
+                ev(Ev::KeyUp, |_| Msg::CursorMoved),
+                ev(Ev::Click, |_| Msg::Complete),
+                ev(Ev::Scroll, |_| Msg::CursorMoved),
+                ev(Ev::Blur, |_| Msg::DismissCompletions)
+            ],
+            completions::view(&model.completions, model.completion)
+        ],
+        " ",
+        button![
+            "=",
+            attrs! {At::AriaLabel => "evaluate"},
+            ev(Ev::Click, |_| Msg::Eval)
+        ],
+        " ",
+        view_preview(
+            &calculator.expression,
+            &model.preview,
+            &calculator.formatter
+        ),
+        " ",
+        span![
+            C![IF!(calculator.is_stale() => "stale")],
+            attrs! {At::AriaLabel => if calculator.is_stale() {
+                format!("result, out of date: {}", calculator.display())
+            } else {
+                format!("result: {}", calculator.display())
+            }},
+            style! {St::Opacity => IF!(calculator.is_stale() => "0.5")},
+            calculator.display()
+        ],
+        IF!(calculator.recognize => calculator.value.as_number().and_then(|value| recognize(value)
+            .first()
+            .map(|candidate| format!(" = {}", candidate.expression)))),
+        " ",
+        select![
+            NOTATIONS.iter().map(|&(value, label, notation)| {
+                let selected = std::mem::discriminant(&notation)
+                    == std::mem::discriminant(&calculator.formatter.notation);
+                option![
+                    attrs! {At::Value => value, At::Selected => selected.as_at_value()},
+                    label
+                ]
+            }),
+            attrs! {At::AriaLabel => "notation"},
+            input_ev(Ev::Change, Msg::NotationChanged)
+        ],
+        digits(calculator.formatter.notation).map(|digits| {
+            let min = if let Notation::Significant(_) = calculator.formatter.notation {
+                1
+            } else {
+                0
+            };
+            input![
+                C!["digits"],
+                attrs! {
+                    At::Type => "number",
+                    At::Min => min,
+                    At::Max => MAX_DIGITS,
+                    At::Value => digits,
+                    At::AriaLabel => "digits",
+                },
+                input_ev(Ev::Change, Msg::DigitsChanged)
+            ]
+        }),
+        " ",
+        select![
+            THOUSANDS_SEPARATORS.iter().map(|&(value, label)| {
+                let selected = calculator.formatter.thousands_separator == value.chars().next();
+                option![
+                    attrs! {At::Value => value, At::Selected => selected.as_at_value()},
+                    label
+                ]
+            }),
+            attrs! {At::AriaLabel => "digit grouping"},
+            input_ev(Ev::Change, Msg::ThousandsSeparatorChanged)
+        ],
+        " ",
+        select![
+            DECIMAL_MARKS.iter().map(|&(value, label)| {
+                let selected = value.starts_with(calculator.formatter.decimal_mark);
+                option![
+                    attrs! {At::Value => value, At::Selected => selected.as_at_value()},
+                    label
+                ]
+            }),
+            attrs! {At::AriaLabel => "decimal mark"},
+            input_ev(Ev::Change, Msg::DecimalMarkChanged)
+        ],
+        " ",
+        select![
+            RADIXES.iter().map(|&(radix, label)| {
+                let selected = calculator.formatter.radix == radix;
+                option![
+                    attrs! {At::Value => radix, At::Selected => selected.as_at_value()},
+                    label
+                ]
+            }),
+            attrs! {At::AriaLabel => "base"},
+            input_ev(Ev::Change, Msg::RadixChanged)
+        ],
+        " ",
+        select![
+            LOCALES.iter().map(|locale| {
+                let selected = *locale == calculator.settings.locale;
+                option![
+                    attrs! {At::Value => locale.tag(), At::Selected => selected.as_at_value()},
+                    locale.tag()
+                ]
+            }),
+            attrs! {At::AriaLabel => "locale"},
+            input_ev(Ev::Change, Msg::LocaleChanged)
+        ],
+        " ",
+        label![
+            input![
+                attrs! {
+                    At::Type => "checkbox",
+                    At::Checked => calculator.recognize.as_at_value()
+                },
+                ev(Ev::Change, |_| Msg::ToggleRecognize)
+            ],
+            "exact"
+        ],
+        " ",
+        select![
+            option![
+                attrs! {At::Value => "rad", At::Selected => (calculator.settings.angle == Angle::Radians).as_at_value()},
+                "rad"
+            ],
+            option![
+                attrs! {At::Value => "deg", At::Selected => (calculator.settings.angle == Angle::Degrees).as_at_value()},
+                "deg"
+            ],
+            attrs! {At::AriaLabel => "angle unit"},
+            input_ev(Ev::Change, Msg::AngleChanged)
+        ],
+        " ",
+        select![
+            option![
+                attrs! {At::Value => "", At::Selected => calculator.settings.word_size.is_none().as_at_value()},
+                "real"
+            ],
+            WORD_BITS.iter().map(|&bits| {
+                let selected = calculator.settings.word_size.map(|word| word.bits()) == Some(bits);
+                option![
+                    attrs! {At::Value => bits, At::Selected => selected.as_at_value()},
+                    format!("{}-bit", bits)
+                ]
+            }),
+            attrs! {At::AriaLabel => "word size"},
+            input_ev(Ev::Change, Msg::WordSizeChanged)
+        ],
+        calculator.settings.word_size.map(|word| {
+            label![
+                input![
+                    attrs! {
+                        At::Type => "checkbox",
+                        At::Checked => word.signed().as_at_value()
+                    },
+                    ev(Ev::Change, |_| Msg::ToggleSigned)
+                ],
+                "signed"
+            ]
+        }),
+        " ",
+        button![
+            if model.theme == Theme::Dark {
+                "light"
+            } else {
+                "dark"
             },
-            input_ev(Ev::Input, Msg::ExpressionChanged)
+            attrs! {At::AriaLabel => "dark theme", At::AriaPressed => (model.theme == Theme::Dark).as_at_value()},
+            ev(Ev::Click, |_| Msg::ToggleTheme)
         ],
         " ",
-        button!["=", ev(Ev::Click, |_| Msg::Eval)],
+        button!["export", ev(Ev::Click, |_| Msg::Export)],
         " ",
-        model.value
+        label![
+            "import ",
+            input![
+                attrs! {At::Type => "file", At::Accept => "application/json"},
+                ev(Ev::Change, |event| {
+                    event
+                        .target()
+                        .and_then(|target| target.dyn_into::<web_sys::HtmlInputElement>().ok())
+                        .map(Msg::Import)
+                })
+            ]
+        ],
+        " ",
+        button!["copy link", ev(Ev::Click, |_| Msg::CopyLink)],
+        " ",
+        label![
+            input![
+                attrs! {
+                    At::Type => "checkbox",
+                    At::Checked => model.share_variables.as_at_value()
+                },
+                ev(Ev::Change, |_| Msg::ToggleShareVariables)
+            ],
+            "with variables"
+        ],
+        error.map(|error| view_error(&calculator.expression, error)),
+        model
+            .plot
+            .as_ref()
+            .map(|plotter| plot::view(plotter, &calculator.formatter)),
+        keypad::view(model.shift, calculator.settings.locale),
+        view_history(calculator),
+        shortcuts::view(),
+        div![
+            C!["sr-only"],
+            attrs! {At::from("role") => "status", At::AriaLive => "polite", At::AriaAtomic => true.as_at_value()},
+            &model.announcement
+        ]
+    ]
+}
+
+fn view_preview(
+    expression: &str,
+    preview: &Option<Result<Value, CalculatorError>>,
+    formatter: &Formatter,
+) -> Node<Msg> {
+    let text = match preview {
+        None => return empty![],
+        Some(Ok(value)) => format!("= {}", formatter.format_value(value)),
+        Some(Err(error)) if error.is_incomplete(expression) => "incomplete expression".to_string(),
+        Some(Err(error)) => error.to_string(),
+    };
+    small![C!["preview"], style! {St::Color => "#767676"}, text]
+}
+
+fn view_error(expression: &str, error: &CalculatorError) -> Node<Msg> {
+    let highlighted = match error.span(expression) {
+        Some(span) => {
+            let marked = &expression[span.clone()];
+            vec![
+                span![&expression[..span.start]],
+                mark![if marked.is_empty() {
+                    "\u{2423}"
+                } else {
+                    marked
+                }],
+                span![&expression[span.end..]],
+            ]
+        }
+        None => vec![span![expression]],
+    };
+    div![
+        C!["error"],
+        id!(ERROR_ID),
+        style! {St::Color => "#a80000"},
+        code![highlighted],
+        " ",
+        error.to_string()
+    ]
+}
+
+fn view_history(calculator: &Calculator) -> Node<Msg> {
+    div![
+        C!["history"],
+        button![
+            "clear",
+            attrs! {At::AriaLabel => "clear history", At::AriaKeyShortcuts => "Control+L"},
+            ev(Ev::Click, |_| Msg::ClearHistory)
+        ],
+        ul![
+            attrs! {At::AriaLabel => "history"},
+            calculator
+                .history
+                .iter()
+                .enumerate()
+                .rev()
+                .map(|(i, entry)| {
+                    let time = js_sys::Date::new(&JsValue::from_f64(entry.timestamp))
+                        .to_locale_time_string("default");
+                    li![
+                        button![
+                            C!["expression"],
+                            attrs! {At::Type => "button", At::Title => "use this expression"},
+                            &entry.expression,
+                            ev(Ev::Click, move |_| Msg::ReuseExpression(i))
+                        ],
+                        " = ",
+                        match &entry.result {
+                            Ok(value) => button![
+                                C!["result"],
+                                attrs! {At::Type => "button", At::Title => "insert this result"},
+                                calculator.formatter.format_value(value),
+                                ev(Ev::Click, move |_| Msg::ReuseResult(i))
+                            ],
+                            Err(error) => span![C!["error"], error.to_string()],
+                        },
+                        " ",
+                        small![String::from(time)],
+                        " ",
+                        button![
+                            "×",
+                            attrs! {At::AriaLabel => format!("delete {}", entry.expression)},
+                            ev(Ev::Click, move |_| Msg::DeleteEntry(i))
+                        ]
+                    ]
+                })
+        ]
     ]
 }
 
-#[wasm_bindgen(start)]
+// Called by the page, so that loading the module for the JavaScript API
+// alone does not mount the app.
+#[wasm_bindgen]
 pub fn start() {
     // Mount the `app` to the element with the `id` "app".
     App::start("app", init, update, view);
@@ -0,0 +1,49 @@
+use super::Msg;
+use calculator::{Completion, CompletionKind};
+
+use seed::{prelude::*, *};
+
+pub const LISTBOX_ID: &str = "completions";
+
+pub fn option_id(index: usize) -> String {
+    format!("completion-{}", index)
+}
+
+fn kind(kind: CompletionKind) -> &'static str {
+    match kind {
+        CompletionKind::Function => "function",
+        CompletionKind::Constant => "constant",
+        CompletionKind::Variable => "variable",
+    }
+}
+
+/// The dropdown under the input, nothing when there is nothing to offer.
+pub fn view(completions: &[Completion], selected: usize) -> Node<Msg> {
+    if completions.is_empty() {
+        return empty![];
+    }
+    ul![
+        C!["completions"],
+        id!(LISTBOX_ID),
+        attrs! {At::from("role") => "listbox"},
+        completions.iter().enumerate().map(|(i, completion)| {
+            li![
+                C![kind(completion.kind), IF!(i == selected => "selected")],
+                id!(option_id(i)),
+                attrs! {
+                    At::from("role") => "option",
+                    At::AriaSelected => (i == selected).as_at_value()
+                },
+                code![&completion.signature],
+                " ",
+                small![&completion.doc],
+                // Mouse down rather than click, so the input keeps its focus.
+                mouse_ev(Ev::MouseDown, move |event| {
+                    event.prevent_default();
+                    Msg::AcceptCompletion(i)
+                }),
+                ev(Ev::MouseEnter, move |_| Msg::SelectCompletion(i))
+            ]
+        })
+    ]
+}
@@ -0,0 +1,50 @@
+use super::Msg;
+use calculator::{matching_paren, Calculator, Highlight};
+
+use seed::{prelude::*, *};
+
+fn class(highlight: Highlight) -> &'static str {
+    match highlight {
+        Highlight::Number => "hl-number",
+        Highlight::Constant => "hl-constant",
+        Highlight::Variable => "hl-variable",
+        Highlight::Function => "hl-function",
+        Highlight::Operator => "hl-operator",
+        Highlight::Paren => "hl-paren",
+        Highlight::Separator => "hl-separator",
+        Highlight::Unknown => "hl-unknown",
+        Highlight::Invalid => "hl-invalid",
+    }
+}
+
+/// The expression as colored text, laid out to sit exactly behind the
+/// input. `cursor` is a byte offset, the parentheses around it are marked.
+pub fn view(
+    calculator: &Calculator,
+    cursor: usize,
+    overlay: &ElRef<web_sys::HtmlElement>,
+) -> Node<Msg> {
+    let expression = &calculator.expression;
+    let spans = calculator.highlight();
+    let pair = matching_paren(&spans, expression, cursor);
+    let mut nodes = vec![];
+    let mut last = 0;
+    for (i, span) in spans.iter().enumerate() {
+        let matched = matches!(pair, Some((open, close)) if i == open || i == close);
+        nodes.push(Node::new_text(
+            expression[last..span.range.start].to_string(),
+        ));
+        nodes.push(span![
+            C![class(span.highlight), IF!(matched => "hl-match")],
+            &expression[span.range.clone()]
+        ]);
+        last = span.range.end;
+    }
+    nodes.push(Node::new_text(expression[last..].to_string()));
+    div![
+        el_ref(overlay),
+        C!["highlight"],
+        attrs! {At::AriaHidden => "true"},
+        nodes
+    ]
+}
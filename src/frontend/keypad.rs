@@ -0,0 +1,192 @@
+use super::{shortcuts, Msg};
+use calculator::Locale;
+
+use seed::{prelude::*, *};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Key {
+    Insert(&'static str),
+    /// The decimal mark of the current locale.
+    Decimal,
+    /// The argument separator of the current locale.
+    Separator,
+    Backspace,
+    Clear,
+    Shift,
+    Eval,
+}
+
+const COLUMNS: usize = 5;
+
+// Both layers share one grid, the second one replaces keys position by position.
+const PRIMARY: [(&str, Key); 35] = [
+    ("2nd", Key::Shift),
+    ("(", Key::Insert("(")),
+    (")", Key::Insert(")")),
+    ("%", Key::Insert("%")),
+    ("⌫", Key::Backspace),
+    ("sin", Key::Insert("sin(")),
+    ("cos", Key::Insert("cos(")),
+    ("ln", Key::Insert("ln(")),
+    ("lg", Key::Insert("lg(")),
+    ("C", Key::Clear),
+    ("√", Key::Insert("√")),
+    ("^", Key::Insert("^")),
+    ("!", Key::Insert("!")),
+    ("π", Key::Insert("π")),
+    ("e", Key::Insert("e")),
+    ("7", Key::Insert("7")),
+    ("8", Key::Insert("8")),
+    ("9", Key::Insert("9")),
+    ("÷", Key::Insert("÷")),
+    ("max", Key::Insert("max(")),
+    ("4", Key::Insert("4")),
+    ("5", Key::Insert("5")),
+    ("6", Key::Insert("6")),
+    ("×", Key::Insert("×")),
+    ("ans", Key::Insert("ans")),
+    ("1", Key::Insert("1")),
+    ("2", Key::Insert("2")),
+    ("3", Key::Insert("3")),
+    ("−", Key::Insert("-")),
+    (",", Key::Separator),
+    ("0", Key::Insert("0")),
+    (".", Key::Decimal),
+    ("EE", Key::Insert("e")),
+    ("+", Key::Insert("+")),
+    ("=", Key::Eval),
+];
+
+const SECOND: [(&str, Key); 35] = [
+    ("2nd", Key::Shift),
+    ("(", Key::Insert("(")),
+    (")", Key::Insert(")")),
+    ("mod", Key::Insert("%")),
+    ("⌫", Key::Backspace),
+    ("asin", Key::Insert("asin(")),
+    ("acos", Key::Insert("acos(")),
+    ("log", Key::Insert("log(")),
+    ("lb", Key::Insert("lb(")),
+    ("C", Key::Clear),
+    ("∛", Key::Insert("∛")),
+    ("x²", Key::Insert("^2")),
+    ("rnd", Key::Insert("rnd(")),
+    ("Inf", Key::Insert("Inf")),
+    ("NaN", Key::Insert("NaN")),
+    ("<", Key::Insert("<")),
+    (">", Key::Insert(">")),
+    ("==", Key::Insert("==")),
+    ("/", Key::Insert("/")),
+    ("min", Key::Insert("min(")),
+    ("and", Key::Insert(" and ")),
+    ("or", Key::Insert(" or ")),
+    ("not", Key::Insert("not ")),
+    ("*", Key::Insert("*")),
+    ("ans1", Key::Insert("ans1")),
+    ("if", Key::Insert("if(")),
+    ("?", Key::Insert("?")),
+    (":", Key::Insert(":")),
+    ("−", Key::Insert("-")),
+    (",", Key::Separator),
+    ("0x", Key::Insert("0x")),
+    ("0b", Key::Insert("0b")),
+    ("EE", Key::Insert("e")),
+    ("+", Key::Insert("+")),
+    ("=", Key::Eval),
+];
+
+// What screen readers say for keys whose label is a symbol.
+fn spoken(label: &str, key: Key) -> Option<&'static str> {
+    Some(match (label, key) {
+        (_, Key::Decimal) => "decimal mark",
+        (_, Key::Separator) => "argument separator",
+        ("2nd", _) => "second functions",
+        ("⌫", _) => "backspace",
+        ("C", _) => "clear",
+        ("(", _) => "open parenthesis",
+        (")", _) => "close parenthesis",
+        ("%", _) | ("mod", _) => "modulo",
+        ("√", _) => "square root",
+        ("∛", _) => "cube root",
+        ("^", _) => "power",
+        ("x²", _) => "squared",
+        ("!", _) => "factorial",
+        ("π", _) => "pi",
+        ("÷", _) | ("/", _) => "divide",
+        ("×", _) | ("*", _) => "multiply",
+        ("−", _) => "minus",
+        ("+", _) => "plus",
+        ("EE", _) => "times ten to the power",
+        ("<", _) => "less than",
+        (">", _) => "greater than",
+        ("==", _) => "equal to",
+        ("?", _) => "then",
+        (":", _) => "else",
+        ("0x", _) => "hexadecimal",
+        ("0b", _) => "binary",
+        ("=", _) => "equals",
+        _ => return None,
+    })
+}
+
+pub fn view(shift: bool, locale: Locale) -> Node<Msg> {
+    let layer = if shift { &SECOND } else { &PRIMARY };
+    div![
+        C!["keypad"],
+        attrs! {At::from("role") => "group", At::AriaLabel => "keypad"},
+        style! {
+            St::Display => "grid",
+            St::GridTemplateColumns => format!("repeat({}, 1fr)", COLUMNS),
+        },
+        layer.iter().map(|&(label, key)| {
+            let optional = |value: Option<&str>| {
+                value.map_or(AtValue::Ignored, |value| AtValue::Some(value.to_string()))
+            };
+            let spoken = optional(spoken(label, key));
+            let shortcut = optional(match key {
+                Key::Insert(text) => shortcuts::for_symbol(text),
+                _ => None,
+            });
+            let pressed = if key == Key::Shift {
+                shift.as_at_value()
+            } else {
+                AtValue::Ignored
+            };
+            let label = match key {
+                Key::Decimal => locale.decimal_mark().to_string(),
+                Key::Separator => locale.argument_separator().to_string(),
+                _ => label.to_string(),
+            };
+            button![
+                C![IF!(key == Key::Shift && shift => "active")],
+                attrs! {
+                    At::Type => "button",
+                    At::AriaLabel => spoken,
+                    At::AriaKeyShortcuts => shortcut,
+                    At::AriaPressed => pressed
+                },
+                label,
+                ev(Ev::Click, move |_| Msg::Key(key))
+            ]
+        })
+    ]
+}
+
+/// Inserts `text` at the byte offset `cursor`, returning the new cursor.
+pub fn insert(expression: &mut String, cursor: usize, text: &str) -> usize {
+    let cursor = cursor.min(expression.len());
+    expression.insert_str(cursor, text);
+    cursor + text.len()
+}
+
+/// Deletes the character before the byte offset `cursor`, returning the new cursor.
+pub fn backspace(expression: &mut String, cursor: usize) -> usize {
+    let cursor = cursor.min(expression.len());
+    match expression[..cursor].char_indices().last() {
+        Some((i, _)) => {
+            expression.remove(i);
+            i
+        }
+        None => cursor,
+    }
+}
@@ -0,0 +1,293 @@
+use super::Msg;
+use calculator::{percentiles, Calculator, CalculatorError, Formatter, Graph, Plot, Segment};
+
+use seed::{prelude::*, *};
+
+const WIDTH: f64 = 600.0;
+const HEIGHT: f64 = 400.0;
+const ZOOM: f64 = 1.2;
+const TICKS: f64 = 8.0;
+const AXIS_COLOR: &str = "#888";
+const COLORS: [&str; 5] = ["#1f77b4", "#d62728", "#2ca02c", "#9467bd", "#ff7f0e"];
+
+/// Pointer input on the canvas, positions are in canvas pixels.
+#[derive(Clone, Copy, Debug)]
+pub enum Event {
+    Press(f64, f64),
+    Move(f64, f64),
+    Release,
+    Leave,
+    /// Zooms around the position, out for a positive delta.
+    Wheel(f64, f64, f64),
+    Reset,
+    Close,
+}
+
+pub struct Plotter {
+    plot: Plot,
+    graphs: Vec<Graph>,
+    segments: Vec<Vec<Segment>>,
+    x: (f64, f64),
+    y: (f64, f64),
+    // Last pointer position while dragging.
+    drag: Option<(f64, f64)>,
+    hover: Option<(f64, Vec<f64>)>,
+    pub canvas: ElRef<web_sys::HtmlCanvasElement>,
+}
+
+impl Plotter {
+    pub fn new(calculator: &Calculator, plot: Plot) -> Result<Self, CalculatorError> {
+        let graphs = plot
+            .functions
+            .iter()
+            .map(|function| calculator.graph(function))
+            .collect::<Result<_, _>>()?;
+        let mut plotter = Plotter {
+            x: (plot.from, plot.to),
+            y: (-1.0, 1.0),
+            plot,
+            graphs,
+            segments: vec![],
+            drag: None,
+            hover: None,
+            canvas: ElRef::default(),
+        };
+        plotter.reset();
+        Ok(plotter)
+    }
+
+    // Back to the requested range with the values fitted vertically.
+    fn reset(&mut self) {
+        self.x = (self.plot.from, self.plot.to);
+        self.resample();
+        let values = self.segments.iter().flatten().flatten().map(|&(_, y)| y);
+        let (low, high) = percentiles(values);
+        let margin = ((high - low) * 0.1).max(0.5);
+        self.y = (low - margin, high + margin);
+    }
+
+    fn resample(&mut self) {
+        let (from, to) = self.x;
+        self.segments = self
+            .graphs
+            .iter_mut()
+            .map(|graph| graph.sample(from, to))
+            .collect();
+    }
+
+    pub fn update(&mut self, event: Event) {
+        match event {
+            Event::Press(px, py) => self.drag = Some((px, py)),
+            Event::Move(px, py) => {
+                if let Some((last_x, last_y)) = self.drag {
+                    let dx = (last_x - px) / WIDTH * (self.x.1 - self.x.0);
+                    let dy = (py - last_y) / HEIGHT * (self.y.1 - self.y.0);
+                    self.x = (self.x.0 + dx, self.x.1 + dx);
+                    self.y = (self.y.0 + dy, self.y.1 + dy);
+                    self.drag = Some((px, py));
+                    self.resample();
+                }
+                let (x, _) = self.world(px, py);
+                let values = self.graphs.iter_mut().map(|graph| graph.at(x)).collect();
+                self.hover = Some((x, values));
+            }
+            Event::Release => self.drag = None,
+            Event::Leave => {
+                self.drag = None;
+                self.hover = None;
+            }
+            Event::Wheel(px, py, delta) => {
+                let factor = if delta > 0.0 { ZOOM } else { 1.0 / ZOOM };
+                let (x, y) = self.world(px, py);
+                self.x = (x + (self.x.0 - x) * factor, x + (self.x.1 - x) * factor);
+                self.y = (y + (self.y.0 - y) * factor, y + (self.y.1 - y) * factor);
+                self.resample();
+            }
+            Event::Reset => self.reset(),
+            Event::Close => {}
+        }
+    }
+
+    fn world(&self, px: f64, py: f64) -> (f64, f64) {
+        (
+            self.x.0 + px / WIDTH * (self.x.1 - self.x.0),
+            self.y.1 - py / HEIGHT * (self.y.1 - self.y.0),
+        )
+    }
+
+    fn pixel(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            (x - self.x.0) / (self.x.1 - self.x.0) * WIDTH,
+            (self.y.1 - y) / (self.y.1 - self.y.0) * HEIGHT,
+        )
+    }
+
+    pub fn draw(&self, formatter: &Formatter) {
+        let canvas = match self.canvas.get() {
+            Some(canvas) => canvas,
+            None => return,
+        };
+        let context = canvas_context_2d(&canvas);
+        context.clear_rect(0.0, 0.0, WIDTH, HEIGHT);
+        self.draw_axes(&context, formatter);
+        for (segments, color) in self.segments.iter().zip(COLORS.iter().cycle()) {
+            context.set_stroke_style(&JsValue::from_str(color));
+            context.set_line_width(2.0);
+            context.begin_path();
+            for segment in segments {
+                for (i, &(x, y)) in segment.iter().enumerate() {
+                    // Keeps poles from producing coordinates the canvas chokes on.
+                    let (px, py) = self.pixel(x, y);
+                    let py = py.clamp(-HEIGHT, 2.0 * HEIGHT);
+                    if i == 0 {
+                        context.move_to(px, py);
+                    } else {
+                        context.line_to(px, py);
+                    }
+                }
+            }
+            context.stroke();
+        }
+        if let Some((x, values)) = &self.hover {
+            let (px, _) = self.pixel(*x, 0.0);
+            context.set_stroke_style(&JsValue::from_str(AXIS_COLOR));
+            context.set_line_width(1.0);
+            context.begin_path();
+            context.move_to(px, 0.0);
+            context.line_to(px, HEIGHT);
+            context.stroke();
+            for (&y, color) in values.iter().zip(COLORS.iter().cycle()) {
+                if y.is_finite() {
+                    let (_, py) = self.pixel(*x, y);
+                    context.set_fill_style(&JsValue::from_str(color));
+                    context.begin_path();
+                    let _ = context.arc(px, py, 3.0, 0.0, std::f64::consts::TAU);
+                    context.fill();
+                }
+            }
+        }
+    }
+
+    fn draw_axes(&self, context: &web_sys::CanvasRenderingContext2d, formatter: &Formatter) {
+        // Axes stick to the border when zero is out of view.
+        let (origin_x, origin_y) = self.pixel(0.0, 0.0);
+        let origin_x = origin_x.clamp(0.0, WIDTH - 1.0);
+        let origin_y = origin_y.clamp(0.0, HEIGHT - 1.0);
+        context.set_stroke_style(&JsValue::from_str(AXIS_COLOR));
+        context.set_fill_style(&JsValue::from_str(AXIS_COLOR));
+        context.set_line_width(1.0);
+        context.set_font("11px sans-serif");
+        context.begin_path();
+        context.move_to(0.0, origin_y);
+        context.line_to(WIDTH, origin_y);
+        context.move_to(origin_x, 0.0);
+        context.line_to(origin_x, HEIGHT);
+        for x in ticks(self.x) {
+            let (px, _) = self.pixel(x, 0.0);
+            context.move_to(px, origin_y - 3.0);
+            context.line_to(px, origin_y + 3.0);
+            if x != 0.0 {
+                let _ = context.fill_text(
+                    &formatter.format(x),
+                    px + 2.0,
+                    (origin_y + 14.0).min(HEIGHT - 2.0),
+                );
+            }
+        }
+        for y in ticks(self.y) {
+            let (_, py) = self.pixel(0.0, y);
+            context.move_to(origin_x - 3.0, py);
+            context.line_to(origin_x + 3.0, py);
+            if y != 0.0 {
+                let _ = context.fill_text(
+                    &formatter.format(y),
+                    (origin_x + 5.0).min(WIDTH - 40.0),
+                    py - 2.0,
+                );
+            }
+        }
+        context.stroke();
+    }
+}
+
+// Multiples of 1, 2 or 5 times a power of ten, about `TICKS` of them.
+fn ticks((from, to): (f64, f64)) -> Vec<f64> {
+    let rough = (to - from) / TICKS;
+    let power = 10f64.powf(rough.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * power)
+        .find(|&step| step >= rough)
+        .unwrap_or(10.0 * power);
+    let first = (from / step).ceil() as i64;
+    let last = (to / step).floor() as i64;
+    (first..=last).map(|i| i as f64 * step).collect()
+}
+
+// Canvas pixels of a mouse event, the canvas may be scaled by CSS.
+fn position(event: &web_sys::MouseEvent) -> (f64, f64) {
+    let scale = event
+        .target()
+        .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+        .map(|canvas| canvas.client_width())
+        .filter(|&width| width > 0)
+        .map_or(1.0, |width| WIDTH / f64::from(width));
+    (
+        f64::from(event.offset_x()) * scale,
+        f64::from(event.offset_y()) * scale,
+    )
+}
+
+pub fn view(plotter: &Plotter, formatter: &Formatter) -> Node<Msg> {
+    div![
+        C!["plot"],
+        canvas![
+            el_ref(&plotter.canvas),
+            attrs! {
+                At::Width => WIDTH,
+                At::Height => HEIGHT,
+                At::AriaLabel => format!("plot of {}", plotter.plot.functions.join(", "))
+            },
+            style! {St::MaxWidth => "100%", St::Cursor => if plotter.drag.is_some() { "grabbing" } else { "crosshair" }},
+            mouse_ev(Ev::MouseDown, |event| {
+                let (px, py) = position(&event);
+                Msg::Plot(Event::Press(px, py))
+            }),
+            mouse_ev(Ev::MouseMove, |event| {
+                let (px, py) = position(&event);
+                Msg::Plot(Event::Move(px, py))
+            }),
+            mouse_ev(Ev::MouseUp, |_| Msg::Plot(Event::Release)),
+            mouse_ev(Ev::MouseLeave, |_| Msg::Plot(Event::Leave)),
+            ev(Ev::Wheel, |event| {
+                event.prevent_default();
+                let event = event.unchecked_into::<web_sys::WheelEvent>();
+                let (px, py) = position(&event);
+                Msg::Plot(Event::Wheel(px, py, event.delta_y()))
+            })
+        ],
+        div![
+            plotter
+                .plot
+                .functions
+                .iter()
+                .zip(COLORS.iter().cycle())
+                .enumerate()
+                .map(|(i, (function, color))| {
+                    let value = plotter
+                        .hover
+                        .as_ref()
+                        .map(|(_, values)| format!(" = {}", formatter.format(values[i])));
+                    span![style! {St::Color => color}, function, value, " "]
+                }),
+            plotter
+                .hover
+                .as_ref()
+                .map(|(x, _)| small![format!("at x = {}", formatter.format(*x))]),
+            " ",
+            button!["reset", ev(Ev::Click, |_| Msg::Plot(Event::Reset))],
+            " ",
+            button!["close", ev(Ev::Click, |_| Msg::Plot(Event::Close))]
+        ]
+    ]
+}
@@ -0,0 +1,67 @@
+use calculator::{is_variable_name, Calculator, Value};
+
+use seed::prelude::*;
+
+const EXPRESSION_KEY: &str = "expr";
+const VARIABLE_KEY: &str = "var";
+
+/// The current page with `expression`, and the variables when asked for,
+/// in the query string. Lists and matrices go along as `name:[1, 2]`.
+pub fn url(calculator: &Calculator, with_variables: bool) -> Url {
+    let mut params = vec![(EXPRESSION_KEY, vec![calculator.expression.clone()])];
+    if with_variables && !(calculator.variables.is_empty() && calculator.lists.is_empty()) {
+        let numbers = calculator
+            .variables
+            .iter()
+            .map(|(name, value)| format!("{}:{}", name, value));
+        let lists = calculator
+            .lists
+            .iter()
+            .map(|(name, value)| format!("{}:{}", name, value));
+        let mut variables: Vec<String> = numbers.chain(lists).collect();
+        variables.sort();
+        params.push((VARIABLE_KEY, variables));
+    }
+    Url::current().set_search(UrlSearch::new(params))
+}
+
+/// Takes the expression and variables found in `url`, returning whether there was an expression.
+/// Variables that would hide a built-in or could not be typed are skipped.
+pub fn restore(url: &Url, calculator: &mut Calculator) -> bool {
+    let search = url.search();
+    if let Some(variables) = search.get(VARIABLE_KEY) {
+        for variable in variables {
+            let mut parts = variable.splitn(2, ':');
+            match (parts.next(), parts.next().map(str::parse)) {
+                (Some(name), Some(Ok(Value::Number(value)))) if is_variable_name(name) => {
+                    calculator.lists.remove(name);
+                    calculator.variables.insert(name.to_string(), value);
+                }
+                (Some(name), Some(Ok(value))) if is_variable_name(name) => {
+                    calculator.variables.remove(name);
+                    calculator.lists.insert(name.to_string(), value);
+                }
+                _ => seed::error!("ignoring shared variable", variable),
+            }
+        }
+    }
+    match search.get(EXPRESSION_KEY).and_then(|values| values.first()) {
+        Some(expression) => {
+            calculator.expression = expression.clone();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Writes `url` to the clipboard. `navigator.clipboard` is looked up
+/// dynamically since `web_sys` keeps it behind unstable APIs.
+pub fn copy(url: &Url) -> Result<(), JsValue> {
+    let navigator = js_sys::Reflect::get(&seed::window(), &"navigator".into())?;
+    let clipboard = js_sys::Reflect::get(&navigator, &"clipboard".into())?;
+    let write_text: js_sys::Function =
+        js_sys::Reflect::get(&clipboard, &"writeText".into())?.dyn_into()?;
+    let href = format!("{}{}", seed::window().location().origin()?, url);
+    write_text.call1(&clipboard, &href.into())?;
+    Ok(())
+}
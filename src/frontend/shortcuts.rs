@@ -0,0 +1,80 @@
+use super::keypad::Key;
+use super::Msg;
+
+use seed::{prelude::*, *};
+
+/// Key combinations and what they do, as listed in the help.
+pub const SHORTCUTS: [(&str, &str); 9] = [
+    ("Enter", "evaluate"),
+    ("Escape", "clear the expression"),
+    ("ArrowUp", "previous expression"),
+    ("ArrowDown", "next expression"),
+    ("Control+L", "clear the history"),
+    ("Alt+R", "insert √"),
+    ("Alt+P", "insert π"),
+    ("Alt+X", "insert ×"),
+    ("Tab", "accept a completion"),
+];
+
+// Symbols with a shortcut, by physical key so that layouts which turn
+// Alt+letter into another character still work.
+const SYMBOLS: [(&str, &str, &str); 3] = [
+    ("KeyR", "Alt+R", "√"),
+    ("KeyP", "Alt+P", "π"),
+    ("KeyX", "Alt+X", "×"),
+];
+
+/// The shortcut that inserts `symbol`, for `aria-keyshortcuts`.
+pub fn for_symbol(symbol: &str) -> Option<&'static str> {
+    SYMBOLS
+        .iter()
+        .find(|&&(_, _, inserted)| inserted == symbol)
+        .map(|&(_, shortcut, _)| shortcut)
+}
+
+/// Shortcuts that work wherever the focus is.
+pub fn global(event: &web_sys::KeyboardEvent) -> Option<Msg> {
+    let code = event.code();
+    let msg = if event.ctrl_key() && !event.alt_key() && code == "KeyL" {
+        Msg::ClearHistory
+    } else if event.alt_key() && !event.ctrl_key() {
+        let &(_, _, symbol) = SYMBOLS.iter().find(|&&(key, _, _)| key == code)?;
+        Msg::Key(Key::Insert(symbol))
+    } else {
+        return None;
+    };
+    event.prevent_default();
+    Some(msg)
+}
+
+/// Keys with a meaning inside the expression input. While completions are
+/// offered, the arrows, Enter, Tab and Escape work on those instead.
+pub fn input(event: &web_sys::KeyboardEvent, completions: usize, selected: usize) -> Option<Msg> {
+    if event.ctrl_key() || event.alt_key() || event.meta_key() {
+        return None;
+    }
+    let open = completions > 0;
+    let msg = match event.key().as_str() {
+        "ArrowDown" if open => Msg::SelectCompletion((selected + 1) % completions),
+        "ArrowUp" if open => Msg::SelectCompletion((selected + completions - 1) % completions),
+        "Enter" | "Tab" if open => Msg::AcceptCompletion(selected),
+        "Escape" if open => Msg::DismissCompletions,
+        "Enter" => Msg::Eval,
+        "Escape" => Msg::Key(Key::Clear),
+        "ArrowUp" => Msg::HistoryUp,
+        "ArrowDown" => Msg::HistoryDown,
+        _ => return None,
+    };
+    event.prevent_default();
+    Some(msg)
+}
+
+pub fn view() -> Node<Msg> {
+    details![
+        C!["shortcuts"],
+        summary!["Keyboard shortcuts"],
+        dl![SHORTCUTS
+            .iter()
+            .map(|&(keys, action)| vec![dt![kbd![keys]], dd![action]])]
+    ]
+}
@@ -0,0 +1,314 @@
+use super::{Model, Theme, DECIMAL_MARKS, MAX_DIGITS, RADIXES, THOUSANDS_SEPARATORS};
+use calculator::{is_variable_name, Angle, CalculatorError, Entry, Locale, Notation, WordSize};
+
+use seed::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+const STORAGE_KEY: &str = "calculator_rs";
+const VERSION: u32 = 2;
+
+// Stored copies of the model. Numbers are kept as strings because JSON has no
+// NaN or Infinity, and both are ordinary results here.
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    version: u32,
+    #[serde(default)]
+    history: Vec<StoredEntry>,
+    #[serde(default)]
+    variables: HashMap<String, String>,
+    /// Variables holding a list or a matrix, as `[1, 2]` or `[[1, 2]]`.
+    #[serde(default)]
+    lists: HashMap<String, String>,
+    #[serde(default)]
+    settings: StoredSettings,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredEntry {
+    expression: String,
+    result: StoredResult,
+    timestamp: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum StoredResult {
+    Value { value: String },
+    Runtime { message: String },
+    Syntax { message: String, position: usize },
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct StoredSettings {
+    notation: StoredNotation,
+    locale: String,
+    angle: String,
+    word_size: Option<(u32, bool)>,
+    radix: Option<u32>,
+    /// `None` in sessions saved before these could be chosen, the locale
+    /// decides then.
+    thousands_separator: Option<Option<char>>,
+    decimal_mark: Option<char>,
+    recognize: bool,
+    theme: String,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum StoredNotation {
+    #[default]
+    Auto,
+    Fixed {
+        digits: usize,
+    },
+    Scientific,
+    Engineering,
+    Significant {
+        digits: usize,
+    },
+}
+
+impl From<Notation> for StoredNotation {
+    fn from(notation: Notation) -> Self {
+        match notation {
+            Notation::Auto => StoredNotation::Auto,
+            Notation::Fixed(digits) => StoredNotation::Fixed { digits },
+            Notation::Scientific => StoredNotation::Scientific,
+            Notation::Engineering => StoredNotation::Engineering,
+            Notation::Significant(digits) => StoredNotation::Significant { digits },
+        }
+    }
+}
+
+impl From<&StoredNotation> for Notation {
+    fn from(notation: &StoredNotation) -> Self {
+        match *notation {
+            StoredNotation::Auto => Notation::Auto,
+            StoredNotation::Fixed { digits } => Notation::Fixed(digits),
+            StoredNotation::Scientific => Notation::Scientific,
+            StoredNotation::Engineering => Notation::Engineering,
+            StoredNotation::Significant { digits } => Notation::Significant(digits),
+        }
+    }
+}
+
+impl Session {
+    pub fn new(model: &Model) -> Self {
+        let calculator = &model.calculator;
+        let history = calculator
+            .history
+            .iter()
+            .map(|entry| StoredEntry {
+                expression: entry.expression.clone(),
+                result: match &entry.result {
+                    Ok(value) => StoredResult::Value {
+                        value: value.to_string(),
+                    },
+                    Err(CalculatorError::Runtime(message)) => StoredResult::Runtime {
+                        message: message.clone(),
+                    },
+                    Err(CalculatorError::Syntax(message, position)) => StoredResult::Syntax {
+                        message: message.clone(),
+                        position: *position,
+                    },
+                },
+                timestamp: entry.timestamp,
+            })
+            .collect();
+        let variables = calculator
+            .variables
+            .iter()
+            .map(|(name, value)| (name.clone(), value.to_string()))
+            .collect();
+        let lists = calculator
+            .lists
+            .iter()
+            .map(|(name, value)| (name.clone(), value.to_string()))
+            .collect();
+        let settings = StoredSettings {
+            notation: calculator.formatter.notation.into(),
+            locale: calculator.settings.locale.tag().to_string(),
+            angle: match calculator.settings.angle {
+                Angle::Radians => "rad",
+                Angle::Degrees => "deg",
+            }
+            .to_string(),
+            word_size: calculator
+                .settings
+                .word_size
+                .map(|word| (word.bits(), word.signed())),
+            radix: Some(calculator.formatter.radix),
+            thousands_separator: Some(calculator.formatter.thousands_separator),
+            decimal_mark: Some(calculator.formatter.decimal_mark),
+            recognize: calculator.recognize,
+            theme: model.theme.name().to_string(),
+        };
+        Session {
+            version: VERSION,
+            history,
+            variables,
+            lists,
+            settings,
+        }
+    }
+
+    pub fn apply(&self, model: &mut Model) {
+        let calculator = &mut model.calculator;
+        calculator.history = self
+            .history
+            .iter()
+            .map(|entry| Entry {
+                expression: entry.expression.clone(),
+                result: match &entry.result {
+                    StoredResult::Value { value } => {
+                        Ok(value.parse().unwrap_or(calculator::Value::Number(f64::NAN)))
+                    }
+                    StoredResult::Runtime { message } => {
+                        Err(CalculatorError::Runtime(message.clone()))
+                    }
+                    StoredResult::Syntax { message, position } => {
+                        Err(CalculatorError::Syntax(message.clone(), *position))
+                    }
+                },
+                timestamp: entry.timestamp,
+            })
+            .collect();
+        calculator.variables = self
+            .variables
+            .iter()
+            .filter(|(name, _)| is_variable_name(name))
+            .filter_map(|(name, value)| Some((name.clone(), value.parse().ok()?)))
+            .collect();
+        calculator.lists = self
+            .lists
+            .iter()
+            .filter(|(name, _)| is_variable_name(name))
+            .filter(|(name, _)| !calculator.variables.contains_key(*name))
+            .filter_map(|(name, value)| match value.parse() {
+                Ok(calculator::Value::Number(_)) | Err(_) => None,
+                Ok(value) => Some((name.clone(), value)),
+            })
+            .collect();
+        let settings = &self.settings;
+        let locale = Locale::from_tag(&settings.locale).unwrap_or_default();
+        calculator.settings.locale = locale;
+        calculator.settings.angle = if settings.angle == "deg" {
+            Angle::Degrees
+        } else {
+            Angle::Radians
+        };
+        calculator.formatter = locale.formatter((&settings.notation).into());
+        if let Some(radix) = settings.radix {
+            calculator.formatter.radix = radix;
+        }
+        calculator.set_word_size(
+            settings
+                .word_size
+                .and_then(|(bits, signed)| WordSize::new(bits, signed)),
+        );
+        if let Some(separator) = settings.thousands_separator {
+            calculator.formatter.thousands_separator = separator;
+        }
+        if let Some(mark) = settings.decimal_mark {
+            calculator.formatter.decimal_mark = mark;
+        }
+        calculator.recognize = settings.recognize;
+        model.theme = Theme::from_name(&settings.theme);
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Reads a session of any known schema version.
+    pub fn from_json(json: &str) -> Option<Self> {
+        migrate(serde_json::from_str(json).ok()?)
+    }
+}
+
+// Older layouts are upgraded step by step here whenever `VERSION` is bumped.
+// Sessions written by a newer version are ignored rather than misread.
+fn migrate(value: Value) -> Option<Session> {
+    let mut session: Session = match value.get("version").and_then(Value::as_u64)? {
+        // Version 1 had no lists, they are left empty.
+        1 | 2 => serde_json::from_value(value).ok()?,
+        _ => return None,
+    };
+    session.settings.drop_invalid();
+    Some(session)
+}
+
+impl StoredSettings {
+    // Sessions come from storage and imported files that anyone can edit.
+    // Settings the app could not have saved fall back to their defaults.
+    fn drop_invalid(&mut self) {
+        let digits_valid = match self.notation {
+            StoredNotation::Fixed { digits } => digits <= MAX_DIGITS,
+            StoredNotation::Significant { digits } => (1..=MAX_DIGITS).contains(&digits),
+            _ => true,
+        };
+        if !digits_valid {
+            self.notation = StoredNotation::Auto;
+        }
+        if let Some((bits, signed)) = self.word_size {
+            if WordSize::new(bits, signed).is_none() {
+                self.word_size = None;
+            }
+        }
+        if let Some(radix) = self.radix {
+            if !RADIXES.iter().any(|&(offered, _)| offered == radix) {
+                self.radix = None;
+            }
+        }
+        let offered = |c: char, choices: &[(&str, &str)]| {
+            choices.iter().any(|(value, _)| value.starts_with(c))
+        };
+        if let Some(Some(separator)) = self.thousands_separator {
+            if !offered(separator, &THOUSANDS_SEPARATORS) || Some(separator) == self.decimal_mark {
+                self.thousands_separator = None;
+            }
+        }
+        if let Some(mark) = self.decimal_mark {
+            if !offered(mark, &DECIMAL_MARKS) {
+                self.decimal_mark = None;
+            }
+        }
+    }
+}
+
+pub fn save(model: &Model) {
+    let _ = LocalStorage::insert(STORAGE_KEY, &Session::new(model));
+}
+
+pub fn load() -> Option<Session> {
+    migrate(LocalStorage::get(STORAGE_KEY).ok()?)
+}
+
+/// Offers the session as a JSON file download.
+pub fn export(model: &Model) {
+    let json = Session::new(model).to_json();
+    let href = format!(
+        "data:application/json;charset=utf-8,{}",
+        String::from(js_sys::encode_uri_component(&json))
+    );
+    let document = seed::document();
+    if let Ok(link) = document.create_element("a") {
+        let _ = link.set_attribute("href", &href);
+        let _ = link.set_attribute("download", "calculator-session.json");
+        if let Ok(link) = link.dyn_into::<web_sys::HtmlElement>() {
+            link.click();
+        }
+    }
+}
+
+/// Reads the first file picked in `input`.
+pub async fn read_file(input: web_sys::HtmlInputElement) -> Option<String> {
+    let file = input.files()?.get(0)?;
+    let text = wasm_bindgen_futures::JsFuture::from(file.text())
+        .await
+        .ok()?;
+    text.as_string()
+}
@@ -0,0 +1,327 @@
+//! The evaluator for other JavaScript code, without the Seed UI. Results and
+//! errors are plain objects, described by the TypeScript below. Positions in
+//! them count UTF-16 code units, like JavaScript strings do.
+
+use calculator::{
+    eval_value_in, highlight, is_variable_name, Angle, Calculator, CalculatorError, Formatter,
+    Highlight, Locale, Notation, Settings, Value, WordSize,
+};
+use js_sys::{Array, Object, Reflect};
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPES: &str = r#"
+export interface EvaluateOptions {
+  /** Unit of trigonometric functions, "radians" by default. */
+  angle?: "radians" | "degrees";
+  /** Decimal mark and argument separator, "en-US" by default. */
+  locale?: "en-US" | "de-DE" | "fr-FR" | "de-CH";
+  /** Wraps every intermediate result to an integer of this size. */
+  wordSize?: { bits: 8 | 16 | 32; signed?: boolean };
+  variables?: Record<string, number>;
+}
+
+export interface CalculatorError {
+  kind: "syntax" | "runtime";
+  message: string;
+  /** Where a syntax error is, missing for runtime errors. */
+  start?: number;
+  end?: number;
+  /** The expression ended too early, like `1+`. */
+  incomplete: boolean;
+}
+
+/** A number, a list, or a matrix as its rows. */
+export type CalculatorValue = number | number[] | number[][];
+
+export type EvaluateResult =
+  | { ok: true; value: CalculatorValue; text: string }
+  | { ok: false; error: CalculatorError };
+
+export interface Token {
+  kind: "number" | "constant" | "variable" | "function" | "operator" | "paren" | "separator" | "unknown" | "invalid";
+  text: string;
+  start: number;
+  end: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "EvaluateOptions")]
+    pub type EvaluateOptions;
+
+    #[wasm_bindgen(typescript_type = "EvaluateResult")]
+    pub type EvaluateResult;
+
+    #[wasm_bindgen(typescript_type = "Token[]")]
+    pub type Tokens;
+
+    #[wasm_bindgen(typescript_type = "Record<string, number>")]
+    pub type Variables;
+}
+
+/// Evaluates `expression` on its own, with `options.variables` as the only
+/// names besides the built-in ones. Throws a `TypeError` for bad options.
+#[wasm_bindgen]
+pub fn evaluate(
+    expression: &str,
+    options: Option<EvaluateOptions>,
+) -> Result<EvaluateResult, JsValue> {
+    let options = Options::read(options.as_deref())?;
+    let variables = options
+        .variables
+        .iter()
+        .map(|(name, &value)| (name.clone(), Value::Number(value)))
+        .collect();
+    let result = eval_value_in(expression, &options.settings, &variables);
+    let formatter = options.settings.locale.formatter(Notation::Auto);
+    Ok(result_object(expression, &result, &formatter))
+}
+
+/// Splits `expression` into tokens, anything the lexer rejects ends up in one
+/// trailing `invalid` token.
+#[wasm_bindgen]
+pub fn tokenize(expression: &str, options: Option<EvaluateOptions>) -> Result<Tokens, JsValue> {
+    let options = Options::read(options.as_deref())?;
+    Ok(tokens(
+        expression,
+        options.settings.locale,
+        &options.variables,
+    ))
+}
+
+/// A calculator that remembers variables and earlier results between
+/// evaluations, like the page does: `x = 2` stores `x`, `ans` is the last
+/// result.
+#[wasm_bindgen]
+pub struct Session {
+    calculator: Calculator,
+}
+
+#[wasm_bindgen]
+impl Session {
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: Option<EvaluateOptions>) -> Result<Session, JsValue> {
+        let options = Options::read(options.as_deref())?;
+        let calculator = Calculator {
+            formatter: options.settings.locale.formatter(Notation::Auto),
+            settings: options.settings,
+            variables: options.variables,
+            ..Calculator::default()
+        };
+        Ok(Session { calculator })
+    }
+
+    pub fn evaluate(&mut self, expression: &str) -> EvaluateResult {
+        self.calculator.expression = expression.to_string();
+        let result = self.calculator.eval(js_sys::Date::now());
+        result_object(expression, &result, &self.calculator.formatter)
+    }
+
+    pub fn tokenize(&self, expression: &str) -> Tokens {
+        let mut scope = self.calculator.answers();
+        scope.extend(self.calculator.variables.clone());
+        tokens(expression, self.calculator.settings.locale, &scope)
+    }
+
+    /// Throws a `TypeError` for a name that could not be typed or would hide
+    /// a built-in.
+    #[wasm_bindgen(js_name = setVariable)]
+    pub fn set_variable(&mut self, name: &str, value: f64) -> Result<(), JsValue> {
+        check_name(name)?;
+        self.calculator.lists.remove(name);
+        self.calculator.variables.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = removeVariable)]
+    pub fn remove_variable(&mut self, name: &str) -> bool {
+        let list = self.calculator.lists.remove(name).is_some();
+        self.calculator.variables.remove(name).is_some() || list
+    }
+
+    /// The stored variables that are numbers, without the `ans` results.
+    pub fn variables(&self) -> Variables {
+        let object = Object::new();
+        for (name, &value) in &self.calculator.variables {
+            set(&object, name, value);
+        }
+        object.unchecked_into()
+    }
+
+    /// Forgets the earlier results, the variables stay.
+    #[wasm_bindgen(js_name = clearHistory)]
+    pub fn clear_history(&mut self) {
+        self.calculator.clear_history();
+    }
+}
+
+struct Options {
+    settings: Settings,
+    variables: HashMap<String, f64>,
+}
+
+impl Options {
+    fn read(options: Option<&JsValue>) -> Result<Self, JsValue> {
+        let mut settings = Settings::default();
+        let mut variables = HashMap::new();
+        let options = match options {
+            Some(options) if !options.is_undefined() && !options.is_null() => options,
+            _ => {
+                return Ok(Options {
+                    settings,
+                    variables,
+                })
+            }
+        };
+        let angle = Reflect::get(options, &"angle".into())?;
+        if !angle.is_undefined() {
+            settings.angle = match angle.as_string().as_deref() {
+                Some("radians") => Angle::Radians,
+                Some("degrees") => Angle::Degrees,
+                _ => return Err(type_error("angle must be \"radians\" or \"degrees\"")),
+            };
+        }
+        let locale = Reflect::get(options, &"locale".into())?;
+        if !locale.is_undefined() {
+            settings.locale = locale
+                .as_string()
+                .and_then(|tag| Locale::from_tag(&tag))
+                .ok_or_else(|| type_error("unknown locale"))?;
+        }
+        let word_size = Reflect::get(options, &"wordSize".into())?;
+        if !word_size.is_undefined() && !word_size.is_null() {
+            let bits = Reflect::get(&word_size, &"bits".into())?.as_f64();
+            let signed = Reflect::get(&word_size, &"signed".into())?
+                .as_bool()
+                .unwrap_or(true);
+            settings.word_size = match bits.filter(|bits| bits.fract() == 0.0) {
+                Some(bits) => WordSize::new(bits as u32, signed),
+                None => None,
+            };
+            if settings.word_size.is_none() {
+                return Err(type_error("wordSize.bits must be 8, 16 or 32"));
+            }
+        }
+        let values = Reflect::get(options, &"variables".into())?;
+        if let Some(values) = values.dyn_ref::<Object>() {
+            for entry in Object::entries(values).iter() {
+                let entry: Array = entry.unchecked_into();
+                let (name, value) = (entry.get(0).as_string(), entry.get(1).as_f64());
+                match (name, value) {
+                    (Some(name), Some(value)) => {
+                        check_name(&name)?;
+                        variables.insert(name, value)
+                    }
+                    _ => return Err(type_error("variables must be numbers")),
+                };
+            }
+        }
+        Ok(Options {
+            settings,
+            variables,
+        })
+    }
+}
+
+fn result_object(
+    expression: &str,
+    result: &Result<Value, CalculatorError>,
+    formatter: &Formatter,
+) -> EvaluateResult {
+    let object = Object::new();
+    match result {
+        Ok(value) => {
+            set(&object, "ok", true);
+            set(&object, "value", value_to_js(value));
+            set(&object, "text", formatter.format_value(value));
+        }
+        Err(error) => {
+            set(&object, "ok", false);
+            set(&object, "error", error_object(expression, error));
+        }
+    }
+    object.unchecked_into()
+}
+
+fn value_to_js(value: &Value) -> JsValue {
+    let numbers =
+        |numbers: &[f64]| -> Array { numbers.iter().map(|&x| JsValue::from(x)).collect() };
+    match value {
+        Value::Number(x) => (*x).into(),
+        Value::List(list) => numbers(list).into(),
+        Value::Matrix(matrix) => (0..matrix.rows())
+            .map(|i| numbers(matrix.row(i)))
+            .collect::<Array>()
+            .into(),
+    }
+}
+
+fn error_object(expression: &str, error: &CalculatorError) -> Object {
+    let object = Object::new();
+    let (kind, message) = match error {
+        CalculatorError::Syntax(message, _) => ("syntax", message),
+        CalculatorError::Runtime(message) => ("runtime", message),
+    };
+    set(&object, "kind", kind);
+    set(&object, "message", message.as_str());
+    if let Some(span) = error.span(expression) {
+        set(&object, "start", utf16_index(expression, span.start));
+        set(&object, "end", utf16_index(expression, span.end));
+    }
+    set(&object, "incomplete", error.is_incomplete(expression));
+    object
+}
+
+fn tokens(expression: &str, locale: Locale, variables: &HashMap<String, f64>) -> Tokens {
+    let tokens: Array = highlight(expression, locale, variables)
+        .into_iter()
+        .map(|span| {
+            let object = Object::new();
+            set(&object, "kind", kind(span.highlight));
+            set(&object, "text", &expression[span.range.clone()]);
+            set(&object, "start", utf16_index(expression, span.range.start));
+            set(&object, "end", utf16_index(expression, span.range.end));
+            object
+        })
+        .collect();
+    tokens.unchecked_into()
+}
+
+fn kind(highlight: Highlight) -> &'static str {
+    match highlight {
+        Highlight::Number => "number",
+        Highlight::Constant => "constant",
+        Highlight::Variable => "variable",
+        Highlight::Function => "function",
+        Highlight::Operator => "operator",
+        Highlight::Paren => "paren",
+        Highlight::Separator => "separator",
+        Highlight::Unknown => "unknown",
+        Highlight::Invalid => "invalid",
+    }
+}
+
+fn set(object: &Object, key: &str, value: impl Into<JsValue>) {
+    // Cannot fail on a fresh plain object.
+    let _ = Reflect::set(object, &key.into(), &value.into());
+}
+
+fn utf16_index(expression: &str, byte_index: usize) -> u32 {
+    expression[..byte_index].encode_utf16().count() as u32
+}
+
+fn check_name(name: &str) -> Result<(), JsValue> {
+    if is_variable_name(name) {
+        Ok(())
+    } else {
+        Err(type_error(&format!("{} cannot be a variable name", name)))
+    }
+}
+
+fn type_error(message: &str) -> JsValue {
+    js_sys::TypeError::new(message).into()
+}
@@ -1,4 +1,4 @@
 #![allow(clippy::wildcard_imports)]
 
-mod calculator;
-mod frontend;
\ No newline at end of file
+mod frontend;
+mod js;